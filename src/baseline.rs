@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::models::{ConflictType, Route, RouteConflict};
+use crate::output::{relative_file, KeyMatching};
+use crate::utils::fnv1a;
+
+/// Name of the baseline file looked up in the project directory when `--baseline` is not given
+pub const BASELINE_FILE_NAME: &str = ".insidious-baseline.json";
+
+/// Version of the baseline file format
+const BASELINE_VERSION: u32 = 1;
+
+/// Conflicts accepted as known, which later analyses no longer report
+#[derive(Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+/// One accepted conflict, identified by a fingerprint that survives line number changes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub conflict_type: ConflictType,
+    pub route1: String,
+    pub file1: String,
+    pub route2: String,
+    pub file2: String,
+}
+
+/// How a baseline was applied to an analysis
+#[derive(Debug, Serialize)]
+pub struct BaselineStatus {
+    pub path: String,
+    /// Number of conflicts hidden because the baseline accepts them
+    pub suppressed: usize,
+    /// Baseline entries that no longer match any conflict
+    pub stale: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// A baseline accepting every given conflict
+    pub fn from_conflicts(conflicts: &[RouteConflict], project_directory: &str) -> Baseline {
+        let mut entries: Vec<BaselineEntry> = conflicts
+            .iter()
+            .map(|conflict| BaselineEntry::new(conflict, project_directory))
+            .collect();
+        entries.sort_by(|a, b| a.fingerprint.cmp(&b.fingerprint));
+
+        Baseline { version: BASELINE_VERSION, entries }
+    }
+
+    pub fn load(path: &Path) -> Result<Baseline, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Error: Could not read baseline file '{}': {}", path.display(), err))?;
+        let baseline: Baseline = serde_json::from_str(&json)
+            .map_err(|err| format!("Error: Invalid baseline file '{}': {}", path.display(), err))?;
+
+        if baseline.version != BASELINE_VERSION {
+            return Err(format!(
+                "Error: Baseline file '{}' has unsupported version {}",
+                path.display(), baseline.version
+            ));
+        }
+
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| format!("Error: Could not serialize the baseline: {}", err))?;
+        fs::write(path, json)
+            .map_err(|err| format!("Error: Could not write baseline file '{}': {}", path.display(), err))
+    }
+
+    /// Remove the conflicts this baseline accepts, returning the rest along with which
+    /// entries were used. Each entry accepts at most one conflict.
+    pub fn apply(
+        &self,
+        conflicts: Vec<RouteConflict>,
+        project_directory: &str,
+        path: &Path,
+    ) -> (Vec<RouteConflict>, BaselineStatus) {
+        let mut unmatched: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate().rev() {
+            unmatched.entry(entry.fingerprint.as_str()).or_default().push(index);
+        }
+
+        let mut remaining = Vec::new();
+        let mut suppressed = 0;
+        for conflict in conflicts {
+            let fingerprint = fingerprint(&conflict, project_directory);
+            match unmatched.get_mut(fingerprint.as_str()).and_then(|indices| indices.pop()) {
+                Some(_) => suppressed += 1,
+                None => remaining.push(conflict),
+            }
+        }
+
+        let mut stale_indices: Vec<usize> = unmatched.into_values().flatten().collect();
+        stale_indices.sort_unstable();
+        let stale = stale_indices.into_iter().map(|index| self.entries[index].clone()).collect();
+
+        let status = BaselineStatus {
+            path: path.display().to_string(),
+            suppressed,
+            stale,
+        };
+        (remaining, status)
+    }
+}
+
+impl BaselineEntry {
+    fn new(conflict: &RouteConflict, project_directory: &str) -> BaselineEntry {
+        BaselineEntry {
+            fingerprint: fingerprint(conflict, project_directory),
+            conflict_type: conflict.conflict_type,
+            route1: route_label(&conflict.route1),
+            file1: relative_file(&conflict.route1, project_directory),
+            route2: route_label(&conflict.route2),
+            file2: relative_file(&conflict.route2, project_directory),
+        }
+    }
+}
+
+/// Conflicts only in `current` and conflicts only in `previous`, matched by the same
+/// fingerprint baselines use so that moving a route does not count as a change
+pub fn conflict_changes<'a>(
+    previous: &'a [RouteConflict],
+    current: &'a [RouteConflict],
+    project_directory: &str,
+) -> (Vec<&'a RouteConflict>, Vec<&'a RouteConflict>) {
+    let previous_keys: Vec<String> = previous.iter().map(|conflict| fingerprint(conflict, project_directory)).collect();
+    let current_keys: Vec<String> = current.iter().map(|conflict| fingerprint(conflict, project_directory)).collect();
+    let matching = KeyMatching::new(&previous_keys, &current_keys);
+
+    (
+        matching.added.iter().map(|&index| &current[index]).collect(),
+        matching.removed.iter().map(|&index| &previous[index]).collect(),
+    )
+}
+
+/// Stable identifier of a conflict built from its type, routes and files, but not its
+/// line numbers, so that unrelated edits do not invalidate the baseline
+pub fn fingerprint(conflict: &RouteConflict, project_directory: &str) -> String {
+    let key = [
+        conflict.conflict_type.as_str().to_string(),
+        route_label(&conflict.route1),
+        relative_file(&conflict.route1, project_directory),
+        route_label(&conflict.route2),
+        relative_file(&conflict.route2, project_directory),
+    ]
+    .join("\n");
+
+    format!("{:016x}", fnv1a(key.as_bytes()))
+}
+
+fn route_label(route: &Route) -> String {
+    format!("{} {}", route.method, route.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Severity;
+
+    fn conflict(path1: &str, line1: usize, path2: &str, line2: usize) -> RouteConflict {
+        let mut route1 = Route::for_tests("GET", path1, 0);
+        route1.line_number = line1;
+        let mut route2 = Route::for_tests("GET", path2, 1);
+        route2.line_number = line2;
+
+        RouteConflict {
+            route1,
+            route2,
+            similarity: 80.0,
+            conflict_type: ConflictType::ShadowedRoute,
+            severity: Severity::Error,
+            sample_url: None,
+        }
+    }
+
+    #[test]
+    fn entries_use_project_relative_files() {
+        let baseline = Baseline::from_conflicts(&[conflict("/users/:id", 1, "/users/me", 2)], "project");
+
+        assert_eq!(baseline.version, BASELINE_VERSION);
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.entries[0].route1, "GET /users/:id");
+        assert_eq!(baseline.entries[0].file1, "app.js");
+        assert_eq!(baseline.entries[0].file2, "app.js");
+    }
+
+    #[test]
+    fn fingerprints_ignore_line_numbers() {
+        let original = fingerprint(&conflict("/users/:id", 1, "/users/me", 2), "project");
+
+        assert_eq!(original, fingerprint(&conflict("/users/:id", 10, "/users/me", 20), "project"));
+        assert_ne!(original, fingerprint(&conflict("/users/:id", 1, "/users/new", 2), "project"));
+
+        let mut moved = conflict("/users/:id", 1, "/users/me", 2);
+        moved.route2.file_path = "project/routes/users.js".to_string();
+        assert_ne!(original, fingerprint(&moved, "project"));
+    }
+
+    #[test]
+    fn apply_accepts_each_entry_once_and_reports_stale_entries() {
+        let baseline = Baseline::from_conflicts(
+            &[conflict("/users/:id", 1, "/users/me", 2), conflict("/a/:x", 3, "/a/b", 4)],
+            "project",
+        );
+        let conflicts = vec![
+            conflict("/users/:id", 5, "/users/me", 6),
+            conflict("/users/:id", 5, "/users/me", 7),
+            conflict("/posts/:id", 8, "/posts/new", 9),
+        ];
+
+        let (remaining, status) = baseline.apply(conflicts, "project", Path::new("baseline.json"));
+
+        assert_eq!(status.suppressed, 1);
+        assert_eq!(remaining.iter().map(|conflict| conflict.route2.line_number).collect::<Vec<_>>(), [7, 9]);
+        assert_eq!(status.stale.iter().map(|entry| entry.route2.as_str()).collect::<Vec<_>>(), ["GET /a/b"]);
+        assert_eq!(status.path, "baseline.json");
+    }
+}
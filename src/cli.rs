@@ -1,5 +1,12 @@
-use clap::{Parser, Subcommand};
-use std::path::Path;
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use crate::baseline::BASELINE_FILE_NAME;
+use crate::config::{self, ConfigLayer, ResolvedConfig};
+use crate::models::Severity;
+use crate::output::OutputFormat;
+use crate::report::ReportFormat;
+use crate::route_pattern::ExpressVersion;
+use crate::similarity::SimilarityAlgorithm;
 
 /// Configuration for route extraction
 pub struct AnalysisConfig {
@@ -7,6 +14,35 @@ pub struct AnalysisConfig {
     pub excluded_dirs: Vec<String>,
     pub file_extensions: Vec<String>,
     pub similarity_threshold: f64,
+    pub similarity_algorithm: SimilarityAlgorithm,
+    pub express_version: ExpressVersion,
+    /// Lines of source captured above and below each route
+    pub context_lines: usize,
+    /// Whether reports include the similarity of every pair of paths above the threshold
+    pub similarity_matrix: bool,
+    /// Whether parse results are cached between runs
+    pub cache: bool,
+    /// Baseline of accepted conflicts to leave out of the results
+    pub baseline: Option<PathBuf>,
+}
+
+#[cfg(test)]
+impl AnalysisConfig {
+    /// Built-in settings for a project in `project`, without source context, cache or baseline
+    pub fn for_tests() -> AnalysisConfig {
+        AnalysisConfig {
+            project_directory: "project".to_string(),
+            excluded_dirs: Vec::new(),
+            file_extensions: vec!["js".to_string()],
+            similarity_threshold: 70.0,
+            similarity_algorithm: SimilarityAlgorithm::default(),
+            express_version: ExpressVersion::default(),
+            context_lines: 0,
+            similarity_matrix: false,
+            cache: false,
+            baseline: None,
+        }
+    }
 }
 
 #[derive(Parser)]
@@ -23,12 +59,31 @@ pub struct AnalysisConfig {
   # Generate a report
   insidious report path/to/project --format html --output report.html
 
+  # Fail a CI build on route conflicts
+  insidious check path/to/project --fail-on warning
+
+  # Accept the current conflicts so that only new ones are reported
+  insidious baseline create path/to/project
+
+  # Compare the routes with an OpenAPI document
+  insidious spec-check path/to/project --spec openapi.yaml
+
+  # Export the routes as an OpenAPI document
+  insidious export openapi path/to/project --output openapi.json
+
   # Initialize a config file
-  insidious init"
+  insidious init
+
+  # Show the effective configuration and where each value comes from
+  insidious config show path/to/project"
 )]
 pub struct CliArgs {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Configuration file to use on top of the project's .express-analyzer.json
+    #[arg(long, global = true)]
+    pub config: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -45,20 +100,150 @@ and routes with similarity above the specified threshold."
     Analyze {
         /// Path to the Express.js project directory
         project_directory: String,
-        
-        /// Directories to exclude from analysis (comma-separated)
-        #[arg(short, long, value_delimiter = ',', default_value = "node_modules")]
-        exclude: Vec<String>,
-        
-        /// File extensions to analyze (comma-separated)
-        #[arg(short = 'x', long = "ext", value_delimiter = ',', default_value = "js")]
-        extensions: Vec<String>,
-        
-        /// Minimum similarity percentage to flag as a conflict
-        #[arg(short, long, default_value = "70")]
-        similarity_threshold: f64,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+
+        #[command(flatten)]
+        baseline: BaselineOptions,
     },
-    
+
+    /// Analyze routes and exit with a non-zero code when findings are found (for CI)
+    #[command(
+        after_help = "EXAMPLE:
+  insidious check ./my-express-app --fail-on warning
+
+EXIT CODES:
+  0  No findings at or above the --fail-on severity
+  1  The project could not be analyzed
+  3  The most severe failing finding is a warning
+  4  The most severe failing finding is an error"
+    )]
+    Check {
+        /// Path to the Express.js project directory
+        project_directory: String,
+
+        /// Lowest severity that fails the check
+        #[arg(long, value_enum, default_value = "error")]
+        fail_on: Severity,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+
+        #[command(flatten)]
+        baseline: BaselineOptions,
+    },
+
+    /// Re-analyze routes whenever project files change and print the conflicts each change introduces or resolves
+    #[command(
+        after_help = "EXAMPLE:
+  insidious watch ./my-express-app
+
+Only files that changed are parsed again. Introduced conflicts are printed with a leading +,
+resolved ones with a leading -. Files in excluded or hidden directories, and files without one
+of the analyzed extensions, are ignored."
+    )]
+    Watch {
+        /// Path to the Express.js project directory
+        project_directory: String,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+
+        #[command(flatten)]
+        baseline: BaselineOptions,
+    },
+
+    /// Compare the routes and conflicts of two git revisions
+    #[command(
+        after_help = "EXAMPLE:
+  insidious diff ./my-express-app --base origin/main --head HEAD --format markdown
+
+Both revisions are read from the git object store, so nothing is checked out and the working
+tree is left untouched. Routes are matched by method and full path; a route is changed when its
+file, router or mount chain differs. Conflicts are matched the way baselines match them."
+    )]
+    Diff {
+        /// Path to the Express.js project directory, inside a git repository
+        #[arg(default_value = ".")]
+        project_directory: String,
+
+        /// Revision to compare from, e.g. a branch, tag or commit
+        #[arg(long)]
+        base: String,
+
+        /// Revision to compare to
+        #[arg(long, default_value = "HEAD")]
+        head: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+    },
+
+    /// Compare the routes of a project with an OpenAPI document
+    #[command(
+        after_help = "EXAMPLE:
+  insidious spec-check ./my-express-app --spec openapi.yaml
+
+Reads an OpenAPI 2 (Swagger) or 3 document in JSON or YAML and lists routes that are implemented
+but undocumented, operations that are documented but not implemented, and routes whose path
+parameters are named differently than in the spec. Paths are compared with the spec's basePath or
+first server path in front, unless --base-path is given.
+
+EXIT CODES:
+  0  The routes and the spec agree
+  1  The project or the spec could not be read
+  5  The routes and the spec disagree"
+    )]
+    SpecCheck {
+        /// Path to the Express.js project directory
+        #[arg(default_value = ".")]
+        project_directory: String,
+
+        /// OpenAPI document to compare with (.json, .yaml or .yml)
+        #[arg(long)]
+        spec: String,
+
+        /// Path prefix of every documented operation, e.g. /api [default: from the spec]
+        #[arg(long)]
+        base_path: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+    },
+
+    /// Compare two saved JSON reports
+    #[command(
+        after_help = "EXAMPLE:
+  insidious compare route_analysis_report.old.json route_analysis_report.json --format markdown
+
+Routes are matched by method and full path, and conflicts by the fingerprint baselines use.
+Matched routes and conflicts whose file or line differs are listed as moved."
+    )]
+    Compare {
+        /// Report from the earlier run
+        old: String,
+
+        /// Report from the later run
+        new: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
+        /// File to write the comparison to instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
     /// Initialize a configuration file
     #[command(
         after_help = "EXAMPLE:
@@ -80,107 +265,370 @@ with default settings for excluded directories, file extensions, and similarity
   
   # Generate a Markdown report
   insidious report ./my-express-app --format markdown --output routes.md
+
+  # Generate a SARIF report for code scanning
+  insidious report ./my-express-app --format sarif --output routes.sarif
+
+  # Generate JUnit XML or Checkstyle XML for CI dashboards
+  insidious report ./my-express-app --format junit --output routes.junit.xml
+  insidious report ./my-express-app --format checkstyle --output routes.checkstyle.xml
+
+  # Generate several formats in one run
+  insidious report ./my-express-app --format json,html,sarif
+
+  # Write a report to standard output
+  insidious report ./my-express-app --format sarif --output -
   
-The tool will analyze the project and generate a report in the specified format.
+The tool will analyze the project and generate a report in each specified format.
+With several formats, give --output once per format, in the same order, or not at all.
 If no output path is provided, the report will be saved to:
   - route_analysis_report.json (for JSON format)
   - route_analysis_report.html (for HTML format)
-  - route_analysis_report.md (for Markdown format)"
+  - route_analysis_report.md (for Markdown format)
+  - route_analysis_report.sarif (for SARIF format)
+  - route_analysis_report.junit.xml (for JUnit format)
+  - route_analysis_report.checkstyle.xml (for Checkstyle format)"
     )]
     Report {
         /// Path to the Express.js project directory
         project_directory: String,
         
-        /// Output format (json, html, markdown)
-        #[arg(short, long, default_value = "json")]
-        format: String,
+        /// Output formats (comma-separated)
+        #[arg(short, long, value_enum, value_delimiter = ',', default_value = "json")]
+        format: Vec<ReportFormat>,
         
-        /// Output file path
+        /// Output file path, or - for standard output; give one per format
+        #[arg(short, long)]
+        output: Vec<String>,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+
+        #[command(flatten)]
+        baseline: BaselineOptions,
+    },
+
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+
+    /// Manage the baseline of accepted conflicts
+    Baseline {
+        #[command(subcommand)]
+        action: BaselineCommand,
+    },
+
+    /// Manage the cache of parsed files
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+
+    /// Export the discovered routes in another format
+    Export {
+        #[command(subcommand)]
+        action: ExportCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration and where each value came from
+    #[command(
+        after_help = "EXAMPLE:
+  INSIDIOUS_SIMILARITY_THRESHOLD=80 insidious config show ./my-express-app --ext js,ts
+
+Configuration is resolved in layers, each overriding the one before:
+  1. Built-in defaults
+  2. .express-analyzer.json in the project directory or its nearest ancestor
+  3. The file given with --config
+  4. INSIDIOUS_EXCLUDE, INSIDIOUS_EXT, INSIDIOUS_SIMILARITY_THRESHOLD,
+     INSIDIOUS_SIMILARITY_ALGORITHM, INSIDIOUS_EXPRESS_VERSION, INSIDIOUS_CONTEXT_LINES
+     and INSIDIOUS_SIMILARITY_MATRIX
+  5. Command line flags"
+    )]
+    Show {
+        /// Path to the Express.js project directory
+        #[arg(default_value = ".")]
+        project_directory: String,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BaselineCommand {
+    /// Record the current conflicts as accepted
+    #[command(
+        after_help = "EXAMPLE:
+  insidious baseline create ./my-express-app
+
+This will write every conflict currently found to .insidious-baseline.json in the project directory.
+Later analyze, check and report runs pick the file up automatically, report only conflicts that are
+not in it, and list baseline entries that no longer occur. Conflicts are identified by their type,
+methods, paths and files, so moving a route to another line does not invalidate the baseline."
+    )]
+    Create {
+        /// Path to the Express.js project directory
+        project_directory: String,
+
+        /// Baseline file to write [default: <project_directory>/.insidious-baseline.json]
         #[arg(short, long)]
         output: Option<String>,
-        
-        /// Directories to exclude from analysis (comma-separated)
-        #[arg(short, long, value_delimiter = ',', default_value = "node_modules")]
-        exclude: Vec<String>,
-        
-        /// File extensions to analyze (comma-separated)
-        #[arg(short = 'x', long = "ext", value_delimiter = ',', default_value = "js")]
-        extensions: Vec<String>,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Delete the cache of parsed files
+    #[command(
+        after_help = "EXAMPLE:
+  insidious cache clear ./my-express-app
+
+Analyses keep the parse result of every file in a cache under $XDG_CACHE_HOME/insidious (or
+~/.cache/insidious) and only parse files again when their size, modification time and content hash
+no longer match. Without a cache directory the cache is .insidious-cache.json in the project directory.
+Clearing the cache makes the next run parse every file."
+    )]
+    Clear {
+        /// Path to the Express.js project directory
+        #[arg(default_value = ".")]
+        project_directory: String,
     },
 }
 
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// Write an OpenAPI 3.1 document describing every route
+    #[command(
+        name = "openapi",
+        after_help = "EXAMPLE:
+  insidious export openapi ./my-express-app --output openapi.json
+
+Each route becomes an operation under its path, with Express parameters such as :id written as
+{id} path parameters. Operations are tagged with their router's mount prefix, or with the app or
+router they are registered on when it is not mounted, and carry an x-source-location extension
+with the route's file, line and column. The title and version come from package.json when present.
+Routes registered with methods OpenAPI 3.1 has no operation for, such as SEARCH, are skipped."
+    )]
+    OpenApi {
+        /// Path to the Express.js project directory
+        #[arg(default_value = ".")]
+        project_directory: String,
+
+        /// Output file path, or - for standard output
+        #[arg(short, long, default_value = "openapi.json")]
+        output: String,
+
+        #[command(flatten)]
+        options: AnalysisOptions,
+    },
+}
+
+/// Options selecting the baseline of accepted conflicts
+#[derive(Args)]
+pub struct BaselineOptions {
+    /// Baseline file of accepted conflicts [default: <project_directory>/.insidious-baseline.json, if present]
+    #[arg(long, conflicts_with = "no_baseline")]
+    pub baseline: Option<String>,
+
+    /// Report every conflict, ignoring any baseline
+    #[arg(long)]
+    pub no_baseline: bool,
+}
+
+/// Analysis options shared by every command that analyzes a project.
+///
+/// Flags left unset fall back to the environment, config files and built-in defaults.
+#[derive(Args)]
+pub struct AnalysisOptions {
+    /// Directories to exclude from analysis (comma-separated) [default: node_modules,dist,build]
+    #[arg(short, long, value_delimiter = ',')]
+    pub exclude: Option<Vec<String>>,
+
+    /// File extensions to analyze (comma-separated) [default: js,ts]
+    #[arg(short = 'x', long = "ext", value_delimiter = ',')]
+    pub extensions: Option<Vec<String>>,
+
+    /// Minimum similarity percentage to flag as a conflict [default: 70]
+    #[arg(short, long)]
+    pub similarity_threshold: Option<f64>,
+
+    /// Algorithm used to score route similarity [default: segment-levenshtein]
+    #[arg(short = 'a', long, value_enum)]
+    pub similarity_algorithm: Option<SimilarityAlgorithm>,
+
+    /// Express major version whose path matching rules apply [default: 4]
+    #[arg(long = "express-version", value_enum)]
+    pub express_version: Option<ExpressVersion>,
+
+    /// Lines of source shown above and below each route in reports [default: 2]
+    #[arg(short = 'C', long)]
+    pub context_lines: Option<usize>,
+
+    /// Include every pair of paths scoring above the similarity threshold in reports
+    #[arg(long, overrides_with = "no_similarity_matrix")]
+    pub similarity_matrix: bool,
+
+    /// Leave the similarity matrix out of reports, even when a config file turns it on
+    #[arg(long, overrides_with = "similarity_matrix")]
+    pub no_similarity_matrix: bool,
+
+    /// Parse every file again instead of reusing cached results
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
 /// Parse command line arguments
 pub fn get_args() -> CliArgs {
     CliArgs::parse()
 }
 
-/// Create an analysis configuration from command arguments
-pub fn create_analysis_config_from_analyze(
-    project_directory: &str,
-    exclude: &[String],
-    extensions: &[String],
-    similarity_threshold: f64,
-) -> AnalysisConfig {
-    AnalysisConfig {
-        project_directory: project_directory.to_string(),
-        excluded_dirs: exclude.to_vec(),
-        file_extensions: extensions.to_vec(),
-        similarity_threshold,
+impl BaselineOptions {
+    /// The baseline file to apply, if any
+    fn baseline_path(&self, project_directory: &str) -> Option<PathBuf> {
+        if self.no_baseline {
+            return None;
+        }
+
+        match &self.baseline {
+            Some(path) => Some(PathBuf::from(path)),
+            None => {
+                let default = Path::new(project_directory).join(BASELINE_FILE_NAME);
+                default.is_file().then_some(default)
+            }
+        }
+    }
+}
+
+impl AnalysisOptions {
+    /// The configuration layer set by these flags
+    fn to_layer(&self) -> ConfigLayer {
+        ConfigLayer {
+            excluded_dirs: self.exclude.clone(),
+            file_extensions: self.extensions.clone(),
+            similarity_threshold: self.similarity_threshold,
+            similarity_algorithm: self.similarity_algorithm,
+            express_version: self.express_version,
+            context_lines: self.context_lines,
+            similarity_matrix: if self.similarity_matrix {
+                Some(true)
+            } else if self.no_similarity_matrix {
+                Some(false)
+            } else {
+                None
+            },
+        }
     }
 }
 
-/// Create an analysis configuration from report arguments
-pub fn create_analysis_config_from_report(
+/// Resolve the layered configuration for a project with the given command line flags
+pub fn resolve_config(
+    args: &CliArgs,
     project_directory: &str,
-    exclude: &[String],
-    extensions: &[String],
-) -> AnalysisConfig {
+    options: &AnalysisOptions,
+) -> Result<ResolvedConfig, String> {
+    config::resolve_config(project_directory, args.config.as_deref(), options.to_layer())
+}
+
+/// Create an analysis configuration from the resolved configuration layers
+pub fn create_analysis_config(project_directory: &str, resolved: ResolvedConfig) -> AnalysisConfig {
     AnalysisConfig {
         project_directory: project_directory.to_string(),
-        excluded_dirs: exclude.to_vec(),
-        file_extensions: extensions.to_vec(),
-        similarity_threshold: 70.0, // Default for reports
+        excluded_dirs: resolved.excluded_dirs.value,
+        file_extensions: resolved.file_extensions.value,
+        similarity_threshold: resolved.similarity_threshold.value,
+        similarity_algorithm: resolved.similarity_algorithm.value,
+        express_version: resolved.express_version.value,
+        context_lines: resolved.context_lines.value,
+        similarity_matrix: resolved.similarity_matrix.value,
+        cache: true,
+        baseline: None,
     }
 }
 
 /// Extract the analysis configuration from command line arguments
-pub fn get_analysis_config(args: &CliArgs) -> AnalysisConfig {
+pub fn get_analysis_config(args: &CliArgs) -> Result<AnalysisConfig, String> {
     match &args.command {
-        Commands::Analyze { 
-            project_directory, 
-            exclude, 
-            extensions, 
-            similarity_threshold 
-        } => {
-            create_analysis_config_from_analyze(
-                project_directory,
-                exclude,
-                extensions,
-                *similarity_threshold,
-            )
+        Commands::Analyze { project_directory, options, baseline }
+        | Commands::Check { project_directory, options, baseline, .. }
+        | Commands::Report { project_directory, options, baseline, .. }
+        | Commands::Watch { project_directory, options, baseline } => {
+            let resolved = resolve_config(args, project_directory, options)?;
+            let mut config = create_analysis_config(project_directory, resolved);
+            config.cache = !options.no_cache;
+            config.baseline = baseline.baseline_path(project_directory);
+            Ok(config)
         },
-        Commands::Report { 
-            project_directory, 
-            exclude,
-            extensions,
-            .. 
-        } => {
-            create_analysis_config_from_report(
-                project_directory,
-                exclude,
-                extensions,
-            )
+        Commands::Baseline { action: BaselineCommand::Create { project_directory, options, .. } }
+        | Commands::Diff { project_directory, options, .. }
+        | Commands::Export { action: ExportCommand::OpenApi { project_directory, options, .. } }
+        | Commands::SpecCheck { project_directory, options, .. } => {
+            // A new baseline records every conflict, a diff compares every conflict of both
+            // revisions, and exports and spec checks only need routes, so no existing baseline applies
+            let resolved = resolve_config(args, project_directory, options)?;
+            let mut config = create_analysis_config(project_directory, resolved);
+            config.cache = !options.no_cache;
+            Ok(config)
         },
-        Commands::Init => {
-            panic!("Analysis config should not be needed for Init command");
+        Commands::Init | Commands::Config { .. } | Commands::Cache { .. } | Commands::Compare { .. } => {
+            panic!("Analysis config should not be needed for this command");
         }
     }
 }
 
+/// Pair each requested report format with its output path, if one was given
+pub fn report_targets<'a>(
+    formats: &[ReportFormat],
+    outputs: &'a [String],
+) -> Result<Vec<(ReportFormat, Option<&'a str>)>, String> {
+    if outputs.is_empty() {
+        return Ok(formats.iter().map(|format| (*format, None)).collect());
+    }
+
+    if outputs.len() != formats.len() {
+        return Err(format!(
+            "Error: {} output path(s) given for {} format(s); give one --output per format or none",
+            outputs.len(), formats.len()
+        ));
+    }
+
+    Ok(formats.iter().zip(outputs).map(|(format, output)| (*format, Some(output.as_str()))).collect())
+}
+
 /// Validate that the project directory exists
 pub fn validate_project_path(path: &str) -> Result<(), String> {
     if !Path::new(path).exists() {
         return Err(format!("Error: Project path '{}' does not exist", path));
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The similarity matrix setting of `insidious config show` with the given flags
+    fn similarity_matrix(flags: &[&str]) -> Option<bool> {
+        let args = CliArgs::try_parse_from(["insidious", "config", "show", "."].iter().chain(flags)).unwrap();
+        let Commands::Config { action: ConfigCommand::Show { options, .. } } = args.command else {
+            unreachable!("parsed a config show command");
+        };
+        options.to_layer().similarity_matrix
+    }
+
+    #[test]
+    fn last_similarity_matrix_flag_wins() {
+        assert_eq!(similarity_matrix(&[]), None);
+        assert_eq!(similarity_matrix(&["--similarity-matrix"]), Some(true));
+        assert_eq!(similarity_matrix(&["--no-similarity-matrix"]), Some(false));
+        assert_eq!(similarity_matrix(&["--similarity-matrix", "--no-similarity-matrix"]), Some(false));
+        assert_eq!(similarity_matrix(&["--no-similarity-matrix", "--similarity-matrix"]), Some(true));
+    }
+}
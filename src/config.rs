@@ -1,11 +1,28 @@
+use std::env;
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
 use serde::{Serialize, Deserialize};
+use crate::route_pattern::ExpressVersion;
+use crate::similarity::SimilarityAlgorithm;
+
+/// Name of the project configuration file, discovered by walking up from the project directory
+pub const CONFIG_FILE_NAME: &str = ".express-analyzer.json";
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub excluded_dirs: Vec<String>,
     pub file_extensions: Vec<String>,
     pub similarity_threshold: f64,
+    #[serde(default)]
+    pub similarity_algorithm: SimilarityAlgorithm,
+    #[serde(default)]
+    pub express_version: ExpressVersion,
+    #[serde(default = "default_context_lines")]
+    pub context_lines: usize,
+    #[serde(default)]
+    pub similarity_matrix: bool,
 }
 
 impl Default for Config {
@@ -14,24 +31,353 @@ impl Default for Config {
             excluded_dirs: vec!["node_modules".to_string(), "dist".to_string(), "build".to_string()],
             file_extensions: vec!["js".to_string(), "ts".to_string()],
             similarity_threshold: 70.0,
+            similarity_algorithm: SimilarityAlgorithm::default(),
+            express_version: ExpressVersion::default(),
+            context_lines: default_context_lines(),
+            similarity_matrix: false,
+        }
+    }
+}
+
+/// Lines of source captured above and below each route
+fn default_context_lines() -> usize {
+    2
+}
+
+/// A partial configuration from one source; unset fields fall through to lower layers
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub excluded_dirs: Option<Vec<String>>,
+    pub file_extensions: Option<Vec<String>>,
+    pub similarity_threshold: Option<f64>,
+    pub similarity_algorithm: Option<SimilarityAlgorithm>,
+    pub express_version: Option<ExpressVersion>,
+    pub context_lines: Option<usize>,
+    pub similarity_matrix: Option<bool>,
+}
+
+/// Where an effective configuration value came from
+#[derive(Debug, Clone)]
+pub enum ConfigSource {
+    Default,
+    ProjectFile(PathBuf),
+    ExplicitFile(PathBuf),
+    Environment(&'static str),
+    CommandLine,
+}
+
+/// A configuration value together with the layer that set it
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+/// The effective configuration after every layer has been applied
+pub struct ResolvedConfig {
+    pub excluded_dirs: Sourced<Vec<String>>,
+    pub file_extensions: Sourced<Vec<String>>,
+    pub similarity_threshold: Sourced<f64>,
+    pub similarity_algorithm: Sourced<SimilarityAlgorithm>,
+    pub express_version: Sourced<ExpressVersion>,
+    pub context_lines: Sourced<usize>,
+    pub similarity_matrix: Sourced<bool>,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "built-in default"),
+            ConfigSource::ProjectFile(path) => write!(f, "project config {}", path.display()),
+            ConfigSource::ExplicitFile(path) => write!(f, "--config {}", path.display()),
+            ConfigSource::Environment(name) => write!(f, "environment variable {}", name),
+            ConfigSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+impl<T> Sourced<T> {
+    fn new(value: T, source: ConfigSource) -> Self {
+        Sourced { value, source }
+    }
+}
+
+impl ResolvedConfig {
+    fn defaults() -> Self {
+        let defaults = Config::default();
+        ResolvedConfig {
+            excluded_dirs: Sourced::new(defaults.excluded_dirs, ConfigSource::Default),
+            file_extensions: Sourced::new(defaults.file_extensions, ConfigSource::Default),
+            similarity_threshold: Sourced::new(defaults.similarity_threshold, ConfigSource::Default),
+            similarity_algorithm: Sourced::new(defaults.similarity_algorithm, ConfigSource::Default),
+            express_version: Sourced::new(defaults.express_version, ConfigSource::Default),
+            context_lines: Sourced::new(defaults.context_lines, ConfigSource::Default),
+            similarity_matrix: Sourced::new(defaults.similarity_matrix, ConfigSource::Default),
+        }
+    }
+
+    /// Override every value the layer sets
+    fn apply(&mut self, layer: ConfigLayer, source: ConfigSource) {
+        if let Some(value) = layer.excluded_dirs {
+            self.excluded_dirs = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.file_extensions {
+            self.file_extensions = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.similarity_threshold {
+            self.similarity_threshold = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.similarity_algorithm {
+            self.similarity_algorithm = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.express_version {
+            self.express_version = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.context_lines {
+            self.context_lines = Sourced::new(value, source.clone());
+        }
+        if let Some(value) = layer.similarity_matrix {
+            self.similarity_matrix = Sourced::new(value, source);
         }
     }
+
+    /// Apply `INSIDIOUS_*` environment variables, looked up with `env_var`, each recorded as its own source
+    fn apply_environment(&mut self, env_var: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+        if let Some(value) = env_var("INSIDIOUS_EXCLUDE") {
+            self.excluded_dirs = Sourced::new(split_list(&value), ConfigSource::Environment("INSIDIOUS_EXCLUDE"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_EXT") {
+            self.file_extensions = Sourced::new(split_list(&value), ConfigSource::Environment("INSIDIOUS_EXT"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_SIMILARITY_THRESHOLD") {
+            let threshold = value.trim().parse().map_err(|_| {
+                format!("Error: INSIDIOUS_SIMILARITY_THRESHOLD must be a number, got '{}'", value)
+            })?;
+            self.similarity_threshold = Sourced::new(threshold, ConfigSource::Environment("INSIDIOUS_SIMILARITY_THRESHOLD"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_SIMILARITY_ALGORITHM") {
+            let algorithm = SimilarityAlgorithm::from_str(value.trim(), true).map_err(|_| {
+                format!("Error: INSIDIOUS_SIMILARITY_ALGORITHM has unknown algorithm '{}'", value)
+            })?;
+            self.similarity_algorithm = Sourced::new(algorithm, ConfigSource::Environment("INSIDIOUS_SIMILARITY_ALGORITHM"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_EXPRESS_VERSION") {
+            let version = ExpressVersion::from_str(value.trim(), true).map_err(|_| {
+                format!("Error: INSIDIOUS_EXPRESS_VERSION must be 4 or 5, got '{}'", value)
+            })?;
+            self.express_version = Sourced::new(version, ConfigSource::Environment("INSIDIOUS_EXPRESS_VERSION"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_CONTEXT_LINES") {
+            let lines = value.trim().parse().map_err(|_| {
+                format!("Error: INSIDIOUS_CONTEXT_LINES must be a non-negative whole number, got '{}'", value)
+            })?;
+            self.context_lines = Sourced::new(lines, ConfigSource::Environment("INSIDIOUS_CONTEXT_LINES"));
+        }
+        if let Some(value) = env_var("INSIDIOUS_SIMILARITY_MATRIX") {
+            let enabled = value.trim().parse().map_err(|_| {
+                format!("Error: INSIDIOUS_SIMILARITY_MATRIX must be true or false, got '{}'", value)
+            })?;
+            self.similarity_matrix = Sourced::new(enabled, ConfigSource::Environment("INSIDIOUS_SIMILARITY_MATRIX"));
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let threshold = &self.similarity_threshold;
+        if !(0.0..=100.0).contains(&threshold.value) {
+            return Err(format!(
+                "Error: similarity threshold must be between 0 and 100, got {} (from {})",
+                threshold.value, threshold.source
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 pub fn init_config() {
     let config = Config::default();
     let json = serde_json::to_string_pretty(&config).unwrap();
-    fs::write(".express-analyzer.json", json).unwrap();
+    fs::write(CONFIG_FILE_NAME, json).unwrap();
+}
+
+/// Resolve the effective configuration from, lowest precedence first: built-in defaults,
+/// the project config file, an explicit `--config` file, environment variables and CLI flags
+pub fn resolve_config(
+    project_directory: &str,
+    explicit_config: Option<&str>,
+    command_line: ConfigLayer,
+) -> Result<ResolvedConfig, String> {
+    resolve_config_with(project_directory, explicit_config, command_line, &env_var)
+}
+
+fn resolve_config_with(
+    project_directory: &str,
+    explicit_config: Option<&str>,
+    command_line: ConfigLayer,
+    env_var: &dyn Fn(&str) -> Option<String>,
+) -> Result<ResolvedConfig, String> {
+    let mut resolved = ResolvedConfig::defaults();
+
+    if let Some(path) = find_project_config(Path::new(project_directory)) {
+        let layer = load_config_layer(&path)?;
+        resolved.apply(layer, ConfigSource::ProjectFile(path));
+    }
+
+    if let Some(path) = explicit_config {
+        let path = PathBuf::from(path);
+        let layer = load_config_layer(&path)?;
+        resolved.apply(layer, ConfigSource::ExplicitFile(path));
+    }
+
+    resolved.apply_environment(env_var)?;
+    resolved.apply(command_line, ConfigSource::CommandLine);
+    resolved.validate()?;
+
+    Ok(resolved)
+}
+
+/// Print every effective setting along with the layer it came from
+pub fn print_resolved_config(project_directory: &str, resolved: &ResolvedConfig) {
+    let rows = [
+        ("excluded_dirs", resolved.excluded_dirs.value.join(","), &resolved.excluded_dirs.source),
+        ("file_extensions", resolved.file_extensions.value.join(","), &resolved.file_extensions.source),
+        ("similarity_threshold", resolved.similarity_threshold.value.to_string(), &resolved.similarity_threshold.source),
+        ("similarity_algorithm", value_name(&resolved.similarity_algorithm.value), &resolved.similarity_algorithm.source),
+        ("express_version", value_name(&resolved.express_version.value), &resolved.express_version.source),
+        ("context_lines", resolved.context_lines.value.to_string(), &resolved.context_lines.source),
+        ("similarity_matrix", resolved.similarity_matrix.value.to_string(), &resolved.similarity_matrix.source),
+    ];
+
+    println!("Effective configuration for: {}\n", project_directory);
+    println!("{:<22} {:<32} SOURCE", "SETTING", "VALUE");
+    for (name, value, source) in rows {
+        println!("{:<22} {:<32} {}", name, value, source);
+    }
+}
+
+/// The name a value is spelled with on the command line and in config files
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value.to_possible_value()
+        .map(|possible| possible.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Find the nearest configuration file in the project directory or one of its ancestors
+fn find_project_config(project_directory: &Path) -> Option<PathBuf> {
+    let start = fs::canonicalize(project_directory).unwrap_or_else(|_| project_directory.to_path_buf());
+
+    start.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+fn load_config_layer(path: &Path) -> Result<ConfigLayer, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("Error: Could not read config file '{}': {}", path.display(), err))?;
+
+    serde_json::from_str(&json)
+        .map_err(|err| format!("Error: Invalid config file '{}': {}", path.display(), err))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
 }
 
-pub fn load_config() -> Config {
-    match fs::read_to_string(".express-analyzer.json") {
-        Ok(json) => {
-            match serde_json::from_str(&json) {
-                Ok(config) => config,
-                Err(_) => Config::default(),
-            }
-        },
-        Err(_) => Config::default(),
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// A project directory with a config file, and a separate explicit config file, under a
+    /// fresh temporary directory
+    fn config_files(name: &str, project_config: &str, explicit_config: &str) -> (String, String) {
+        let root = env::temp_dir().join(format!("insidious-config-{}-{}", name, std::process::id()));
+        let project = root.join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join(CONFIG_FILE_NAME), project_config).unwrap();
+        fs::write(root.join("explicit.json"), explicit_config).unwrap();
+
+        (project.to_string_lossy().into_owned(), root.join("explicit.json").to_string_lossy().into_owned())
+    }
+
+    fn environment(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+        move |name| vars.get(name).cloned()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let (project, explicit) = config_files(
+            "precedence",
+            r#"{"similarity_threshold": 50, "context_lines": 4, "express_version": "5", "file_extensions": ["ts"]}"#,
+            r#"{"similarity_threshold": 60, "context_lines": 6, "express_version": "4"}"#,
+        );
+        let env = environment(&[("INSIDIOUS_SIMILARITY_THRESHOLD", "80"), ("INSIDIOUS_CONTEXT_LINES", "8")]);
+        let command_line = ConfigLayer { similarity_threshold: Some(90.0), ..ConfigLayer::default() };
+
+        let resolved = resolve_config_with(&project, Some(&explicit), command_line, &env).unwrap();
+
+        assert_eq!(resolved.excluded_dirs.value, Config::default().excluded_dirs);
+        assert!(matches!(resolved.excluded_dirs.source, ConfigSource::Default));
+        assert_eq!(resolved.file_extensions.value, ["ts"]);
+        assert!(matches!(resolved.file_extensions.source, ConfigSource::ProjectFile(_)));
+        assert_eq!(resolved.express_version.value, ExpressVersion::Express4);
+        assert!(matches!(resolved.express_version.source, ConfigSource::ExplicitFile(_)));
+        assert_eq!(resolved.context_lines.value, 8);
+        assert!(matches!(resolved.context_lines.source, ConfigSource::Environment("INSIDIOUS_CONTEXT_LINES")));
+        assert_eq!(resolved.similarity_threshold.value, 90.0);
+        assert!(matches!(resolved.similarity_threshold.source, ConfigSource::CommandLine));
+    }
+
+    #[test]
+    fn command_line_can_turn_off_a_configured_similarity_matrix() {
+        let (project, explicit) = config_files("matrix", r#"{"similarity_matrix": true}"#, "{}");
+        let env = environment(&[]);
+
+        let configured = resolve_config_with(&project, Some(&explicit), ConfigLayer::default(), &env).unwrap();
+        assert!(configured.similarity_matrix.value);
+
+        let command_line = ConfigLayer { similarity_matrix: Some(false), ..ConfigLayer::default() };
+        let overridden = resolve_config_with(&project, Some(&explicit), command_line, &env).unwrap();
+        assert!(!overridden.similarity_matrix.value);
+        assert!(matches!(overridden.similarity_matrix.source, ConfigSource::CommandLine));
+    }
+
+    #[test]
+    fn invalid_environment_values_are_errors() {
+        let (project, explicit) = config_files("environment", "{}", "{}");
+
+        for (name, value) in [
+            ("INSIDIOUS_SIMILARITY_THRESHOLD", "high"),
+            ("INSIDIOUS_SIMILARITY_ALGORITHM", "soundex"),
+            ("INSIDIOUS_EXPRESS_VERSION", "3"),
+            ("INSIDIOUS_CONTEXT_LINES", "-1"),
+            ("INSIDIOUS_SIMILARITY_MATRIX", "yes"),
+        ] {
+            let env = environment(&[(name, value)]);
+            let err = resolve_config_with(&project, Some(&explicit), ConfigLayer::default(), &env).err();
+            assert!(err.is_some_and(|err| err.contains(name)), "{} = {} was accepted", name, value);
+        }
+    }
+
+    #[test]
+    fn invalid_config_files_are_errors() {
+        let (project, explicit) = config_files("files", r#"{"similarity_treshold": 50}"#, "not json");
+        let env = environment(&[]);
+
+        let unknown_field = resolve_config_with(&project, None, ConfigLayer::default(), &env).err().unwrap();
+        assert!(unknown_field.contains("similarity_treshold"));
+
+        let (project, _) = config_files("files-valid", "{}", "{}");
+        assert!(resolve_config_with(&project, Some(&explicit), ConfigLayer::default(), &env).is_err());
+    }
+}
@@ -1,60 +1,231 @@
-use crate::models::{Route, RouteConflict};
-use crate::utils::calculate_path_similarity;
+use std::collections::HashSet;
+use rayon::prelude::*;
+use crate::models::{ConflictType, Route, RouteConflict, SuppressedConflict, Suppression};
+use crate::route_index::RouteIndex;
+use crate::route_pattern::RoutePattern;
+use crate::similarity::path_similarity;
 use crate::cli::AnalysisConfig;
 
-pub fn check_route_conflicts(routes: &[Route], config: &AnalysisConfig) -> Vec<RouteConflict> {
+/// Conflicts found between routes, split by whether a suppression comment accepts them
+pub struct ConflictAnalysis {
+    pub conflicts: Vec<RouteConflict>,
+    pub suppressed: Vec<SuppressedConflict>,
+    /// Suppression comments that did not suppress any conflict
+    pub unused_suppressions: Vec<Suppression>,
+}
+
+/// Find conflicts between routes. Conflicts involving a route covered by a matching
+/// suppression comment are set aside, and `suppressions` not needed by any conflict are reported.
+pub fn check_route_conflicts(
+    routes: &[Route],
+    suppressions: &[Suppression],
+    config: &AnalysisConfig,
+) -> ConflictAnalysis {
+    // Express tries handlers in registration order, so the earlier route always comes first
+    let mut routes: Vec<&Route> = routes.iter().collect();
+    routes.sort_by_key(|route| route.order);
+
+    let patterns: Vec<RoutePattern> = routes
+        .par_iter()
+        .map(|route| {
+            RoutePattern::parse(&route.path, config.express_version).unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: {} ({}:{}); comparing it as a literal path",
+                    err, route.file_path, route.line_number
+                );
+                RoutePattern::literal(&route.path)
+            })
+        })
+        .collect();
+    let index = RouteIndex::new(routes.iter().map(|route| route.path.as_str()));
+
+    // Only routes sharing a branch of the index can match the same URL
+    let findings: Vec<Vec<(RouteConflict, Vec<&Suppression>)>> = (0..routes.len())
+        .into_par_iter()
+        .map(|i| {
+            index.candidates(&routes[i].path)
+                .into_iter()
+                .filter(|&j| j > i)
+                .filter_map(|j| find_conflict(routes[i], routes[j], &patterns[i], &patterns[j], config))
+                .collect()
+        })
+        .collect();
+
     let mut conflicts = Vec::new();
-    
-    for (i, route1) in routes.iter().enumerate() {
-        for route2 in routes.iter().skip(i + 1) {
-            if route1.method != route2.method {
-                continue;
-            }
-            
-            let similarity = calculate_path_similarity(&route1.path, &route2.path);
-            
-            if route1.path == route2.path {
-                conflicts.push(create_conflict(
-                    route1.clone(), 
-                    route2.clone(), 
-                    100.0, 
-                    "Exact Match"
-                ));
-                continue;
-            }
-            
-            let path1_parts: Vec<&str> = route1.path.split('/').collect();
-            let path2_parts: Vec<&str> = route2.path.split('/').collect();
-            
-            if path1_parts.len() == path2_parts.len() {
-                let mut potential_conflict = false;
-                for (p1, p2) in path1_parts.iter().zip(path2_parts.iter()) {
-                    if (p1.starts_with(':') || p2.starts_with(':')) && p1 != p2 {
-                        potential_conflict = true;
-                        break;
-                    }
-                }
-                
-                if potential_conflict && similarity > config.similarity_threshold {
-                    conflicts.push(create_conflict(
-                        route1.clone(), 
-                        route2.clone(), 
-                        similarity, 
-                        "Parameter Conflict"
-                    ));
-                }
-            }
+    let mut suppressed = Vec::new();
+    // Every comment that applies to a conflict is needed, not just the one it is listed under
+    let mut used: HashSet<(&str, usize)> = HashSet::new();
+    for (conflict, applicable) in findings.into_iter().flatten() {
+        match applicable.first() {
+            Some(suppression) => suppressed.push(SuppressedConflict {
+                suppression: (*suppression).clone(),
+                conflict,
+            }),
+            None => conflicts.push(conflict),
         }
+        used.extend(applicable.into_iter().map(suppression_location));
+    }
+
+    let unused_suppressions = suppressions
+        .iter()
+        .filter(|suppression| !used.contains(&suppression_location(suppression)))
+        .cloned()
+        .collect();
+
+    ConflictAnalysis { conflicts, suppressed, unused_suppressions }
+}
+
+/// Identifies a suppression comment: no two comments share a file and line
+fn suppression_location(suppression: &Suppression) -> (&str, usize) {
+    (&suppression.file_path, suppression.line_number)
+}
+
+/// The conflict between two routes, if any, along with the suppression comments covering it
+fn find_conflict<'a>(
+    route1: &'a Route,
+    route2: &'a Route,
+    pattern1: &RoutePattern,
+    pattern2: &RoutePattern,
+    config: &AnalysisConfig,
+) -> Option<(RouteConflict, Vec<&'a Suppression>)> {
+    if !methods_overlap(&route1.method, &route2.method) {
+        return None;
     }
-    
-    conflicts
+
+    let sample_url = match pattern1.common_url(pattern2) {
+        Ok(url) => url?,
+        Err(err) => {
+            warn_inconclusive(route1, route2, "overlap", &err);
+            return None;
+        }
+    };
+    let similarity = path_similarity(&route1.path, &route2.path, config.similarity_algorithm);
+
+    // Express only tries the handlers of one app (or unmounted router) for a request
+    let same_tree = route1.root == route2.root;
+    let shadows = same_tree && method_covers(&route1.method, &route2.method) && match pattern1.covers(pattern2) {
+        Ok(covers) => covers,
+        Err(err) => {
+            warn_inconclusive(route1, route2, "shadows the other", &err);
+            false
+        }
+    };
+
+    let conflict_type = if same_tree && route1.path == route2.path && route1.method == route2.method {
+        ConflictType::ExactMatch
+    } else if shadows {
+        ConflictType::ShadowedRoute
+    } else if similarity > config.similarity_threshold {
+        ConflictType::ParameterConflict
+    } else {
+        return None;
+    };
+
+    let conflict = create_conflict(
+        route1.clone(),
+        route2.clone(),
+        if conflict_type == ConflictType::ExactMatch { 100.0 } else { similarity },
+        conflict_type,
+        sample_url,
+    );
+
+    let applicable = route1.suppressions.iter()
+        .chain(&route2.suppressions)
+        .filter(|suppression| suppression.applies_to(conflict_type))
+        .collect();
+
+    Some((conflict, applicable))
+}
+
+/// Tell the user a pair of routes could not be compared, instead of silently treating it as unrelated
+fn warn_inconclusive(route1: &Route, route2: &Route, question: &str, err: &str) {
+    eprintln!(
+        "Warning: Could not decide whether {} {} ({}:{}) and {} {} ({}:{}) {}: {}",
+        route1.method, route1.path, route1.file_path, route1.line_number,
+        route2.method, route2.path, route2.file_path, route2.line_number,
+        question, err
+    );
 }
 
-fn create_conflict(route1: Route, route2: Route, similarity: f64, conflict_type: &str) -> RouteConflict {
+/// Whether some request method is handled by both routes.
+///
+/// `ALL` handles every method, and Express answers `HEAD` requests with `GET` handlers.
+fn methods_overlap(method1: &str, method2: &str) -> bool {
+    method1 == method2
+        || method1 == "ALL"
+        || method2 == "ALL"
+        || matches!((method1, method2), ("GET", "HEAD") | ("HEAD", "GET"))
+}
+
+/// Whether every request method handled by the `later` route is also handled by the `earlier` one
+fn method_covers(earlier: &str, later: &str) -> bool {
+    earlier == later || earlier == "ALL" || (earlier == "GET" && later == "HEAD")
+}
+
+fn create_conflict(
+    route1: Route,
+    route2: Route,
+    similarity: f64,
+    conflict_type: ConflictType,
+    sample_url: String,
+) -> RouteConflict {
     RouteConflict {
         route1,
         route2,
         similarity,
-        conflict_type: conflict_type.to_string(),
+        conflict_type,
+        severity: conflict_type.severity(),
+        sample_url: Some(sample_url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MountPoint;
+
+    fn conflict_types(routes: &[Route]) -> Vec<ConflictType> {
+        check_route_conflicts(routes, &[], &AnalysisConfig::for_tests())
+            .conflicts
+            .iter()
+            .map(|conflict| conflict.conflict_type)
+            .collect()
+    }
+
+    #[test]
+    fn parameter_route_shadows_a_later_literal_route() {
+        let routes = [Route::for_tests("GET", "/users/:id", 0), Route::for_tests("GET", "/users/me", 1)];
+
+        assert_eq!(conflict_types(&routes), [ConflictType::ShadowedRoute]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn literal_route_registered_first_is_not_shadowing() {
+        let routes = [Route::for_tests("GET", "/users/me", 0), Route::for_tests("GET", "/users/:id", 1)];
+
+        assert_eq!(conflict_types(&routes), [ConflictType::ParameterConflict]);
+    }
+
+    #[test]
+    fn all_route_shadows_a_later_get_but_not_the_reverse() {
+        let all_first = [Route::for_tests("ALL", "/health", 0), Route::for_tests("GET", "/health", 1)];
+        assert_eq!(conflict_types(&all_first), [ConflictType::ShadowedRoute]);
+
+        let get_first = [Route::for_tests("GET", "/health", 0), Route::for_tests("ALL", "/health", 1)];
+        assert_eq!(conflict_types(&get_first), [ConflictType::ParameterConflict]);
+    }
+
+    #[test]
+    fn router_mounted_after_a_catch_all_is_shadowed() {
+        let mut users = Route::for_tests("GET", "/api/users", 1);
+        users.router = "router".to_string();
+        users.mount_chain = vec![MountPoint {
+            prefix: "/api".to_string(),
+            file_path: "project/app.js".to_string(),
+            line_number: 2,
+        }];
+        let routes = [Route::for_tests("GET", "*", 0), users];
+
+        assert_eq!(conflict_types(&routes), [ConflictType::ShadowedRoute]);
+    }
+}
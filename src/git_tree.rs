@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use crate::cli::AnalysisConfig;
+use crate::route_extractor::{has_source_extension, is_included, SourceTree};
+use crate::utils::normalize_path;
+
+/// The project's files as of a git revision, read from the object store without a checkout
+pub struct GitTree {
+    /// Every file in the revision, as paths under the project directory
+    files: HashSet<PathBuf>,
+    /// Contents of the source files
+    sources: BTreeMap<PathBuf, String>,
+}
+
+impl GitTree {
+    /// Read the files under the project directory as they are at `revision`
+    pub fn load(config: &AnalysisConfig, revision: &str) -> Result<GitTree, String> {
+        let project_directory = Path::new(&config.project_directory);
+
+        // Run inside the project directory, so that paths are listed relative to it
+        let listing = git(project_directory, &["ls-tree", "-r", "-z", revision], None)
+            .map_err(|err| format!("Error: Could not read revision '{}': {}", revision, err))?;
+
+        let mut files = HashSet::new();
+        let mut blobs = Vec::new();
+        for entry in listing.split(|&byte| byte == 0).filter(|entry| !entry.is_empty()) {
+            let entry = String::from_utf8_lossy(entry);
+            // Each entry is "<mode> <type> <object>\t<path>"
+            let Some((meta, relative)) = entry.split_once('\t') else {
+                continue;
+            };
+            let fields: Vec<&str> = meta.split(' ').collect();
+            if fields.len() != 3 || fields[1] != "blob" || fields[0] == "120000" {
+                continue;
+            }
+
+            let path = normalize_path(&project_directory.join(relative));
+            if is_included(&path, config) && has_source_extension(&path, config) {
+                blobs.push((path.clone(), fields[2].to_string()));
+            }
+            files.insert(path);
+        }
+
+        let contents = read_blobs(project_directory, blobs.iter().map(|(_, object)| object.as_str()))
+            .map_err(|err| format!("Error: Could not read files of revision '{}': {}", revision, err))?;
+        let sources = blobs.into_iter().map(|(path, _)| path).zip(contents).collect();
+
+        Ok(GitTree { files, sources })
+    }
+}
+
+impl SourceTree for GitTree {
+    fn source_files(&self, root: &Path, _config: &AnalysisConfig) -> Vec<PathBuf> {
+        let root = normalize_path(root);
+        self.sources.keys().filter(|path| path.starts_with(&root)).cloned().collect()
+    }
+
+    fn read_to_string(&self, path: &Path) -> Option<String> {
+        self.sources.get(&normalize_path(path)).cloned()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains(&normalize_path(path))
+    }
+}
+
+/// Read the contents of the given blobs with a single `git cat-file --batch`
+fn read_blobs<'a>(directory: &Path, objects: impl Iterator<Item = &'a str>) -> Result<Vec<String>, String> {
+    let request: String = objects.map(|object| format!("{}\n", object)).collect();
+    let count = request.lines().count();
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let output = git(directory, &["cat-file", "--batch"], Some(request))?;
+    parse_batch_output(&output, count)
+}
+
+/// Split the output of `git cat-file --batch` into the contents of `count` objects
+fn parse_batch_output(output: &[u8], count: usize) -> Result<Vec<String>, String> {
+    let mut reader = BufReader::new(output);
+    let mut contents = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        // Each object is "<object> <type> <size>\n<content>\n", or "<object> missing\n"
+        let mut header = String::new();
+        reader.read_line(&mut header).map_err(|err| err.to_string())?;
+        let header = header.trim_end();
+        if let Some(object) = header.strip_suffix(" missing") {
+            return Err(format!("object '{}' is missing", object));
+        }
+        let size: usize = header
+            .rsplit(' ')
+            .next()
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(|| format!("unexpected git output '{}'", header))?;
+
+        let mut content = vec![0; size + 1];
+        reader.read_exact(&mut content).map_err(|err| err.to_string())?;
+        content.pop();
+        contents.push(String::from_utf8_lossy(&content).into_owned());
+    }
+
+    Ok(contents)
+}
+
+/// Run git in `directory` and return its standard output
+fn git(directory: &Path, args: &[&str], input: Option<String>) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("could not run git: {}", err))?;
+
+    // Feed the input from another thread so that a full output pipe cannot block git
+    let writer = input.map(|input| {
+        let mut stdin = child.stdin.take().unwrap();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if let Some(writer) = writer {
+        writer.join()
+            .map_err(|_| "could not write to git".to_string())?
+            .map_err(|err| err.to_string())?;
+    }
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_output_is_split_by_object_size() {
+        let first = "app.get('/users', list);\n";
+        let second = "// héllo\n\napp.use('/api', api);";
+        let output = format!(
+            "1111 blob {}\n{}\n2222 blob {}\n{}\n",
+            first.len(),
+            first,
+            second.len(),
+            second
+        );
+
+        assert_eq!(parse_batch_output(output.as_bytes(), 2).unwrap(), [first, second]);
+        assert_eq!(parse_batch_output(b"3333 blob 0\n\n", 1).unwrap(), [""]);
+    }
+
+    #[test]
+    fn missing_objects_and_truncated_output_are_errors() {
+        let output = b"1111 blob 2\nok\n2222 missing\n";
+        assert_eq!(parse_batch_output(output, 2).unwrap_err(), "object '2222' is missing");
+
+        assert!(parse_batch_output(b"1111 blob 10\nshort\n", 1).is_err());
+        assert_eq!(parse_batch_output(b"1111 blob 2\nok\n", 2).unwrap_err(), "unexpected git output ''");
+    }
+}
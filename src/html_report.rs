@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::models::{AnalysisReport, Route, RouteConflict, Severity};
+use crate::similarity::path_similarity;
+
+/// Page layout, styles and scripts; `{{name}}` placeholders are filled with rendered sections
+const TEMPLATE: &str = include_str!("html_report.html");
+
+/// Largest number of distinct paths drawn in the similarity heatmap
+const HEATMAP_LIMIT: usize = 60;
+
+/// A node of the route tree: one path segment and the routes that end at it
+#[derive(Default)]
+struct TreeNode<'r> {
+    children: BTreeMap<String, TreeNode<'r>>,
+    routes: Vec<&'r Route>,
+}
+
+/// Render a self-contained HTML report with no external assets
+pub fn render_html(report: &AnalysisReport) -> Result<String, String> {
+    let sections = HashMap::from([
+        ("subtitle", subtitle(report)),
+        ("summary", summary(report)),
+        ("conflicts", conflicts(report)),
+        ("notices", notices(report)),
+        ("routes", routes_table(report)),
+        ("tree", route_tree(report)),
+        ("heatmap", heatmap(report)),
+    ]);
+
+    fill_template(TEMPLATE, &sections)
+}
+
+/// Replace every `{{name}}` placeholder in a single pass, so inserted content is never rescanned
+fn fill_template(template: &str, sections: &HashMap<&str, String>) -> Result<String, String> {
+    let mut html = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").map(|end| start + end)
+            .ok_or_else(|| "unterminated placeholder in HTML template".to_string())?;
+        let name = &rest[start + 2..end];
+        let section = sections.get(name)
+            .ok_or_else(|| format!("unknown placeholder '{}' in HTML template", name))?;
+
+        html.push_str(&rest[..start]);
+        html.push_str(section);
+        rest = &rest[end + 2..];
+    }
+    html.push_str(rest);
+
+    Ok(html)
+}
+
+fn subtitle(report: &AnalysisReport) -> String {
+    escape(&format!(
+        "{} routes in {} files, {} conflicts",
+        report.total_routes, report.inventory.files.len(), report.conflict_count
+    ))
+}
+
+fn summary(report: &AnalysisReport) -> String {
+    let errors = report.conflicts.iter().filter(|c| c.severity == Severity::Error).count();
+    let mut stats = vec![
+        ("Total Routes", report.total_routes),
+        ("Conflicts", report.conflict_count),
+        ("Errors", errors),
+        ("Warnings", report.conflict_count - errors),
+    ];
+    if !report.suppressed_conflicts.is_empty() {
+        stats.push(("Suppressed", report.suppressed_conflicts.len()));
+    }
+    if let Some(baseline) = &report.baseline {
+        stats.push(("Baselined", baseline.suppressed));
+    }
+
+    let mut html = String::from("<div class=\"stats\">\n");
+    for (label, value) in stats {
+        html.push_str(&format!(
+            "<div class=\"stat\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>\n",
+            value, label
+        ));
+    }
+    html.push_str("</div>\n");
+
+    for (label, counts) in [("By method", &report.inventory.method_counts), ("By prefix", &report.inventory.prefix_counts)] {
+        if counts.is_empty() {
+            continue;
+        }
+        let items: Vec<String> = counts.iter()
+            .map(|(name, count)| format!("<code>{}</code> {}", escape(name), count))
+            .collect();
+        html.push_str(&format!("<p class=\"counts\">{}: {}</p>\n", label, items.join(", ")));
+    }
+
+    html
+}
+
+fn conflicts(report: &AnalysisReport) -> String {
+    if report.conflicts.is_empty() {
+        return "<p class=\"muted\">No conflicts found.</p>".to_string();
+    }
+
+    let mut html = String::from(
+        "<input class=\"filter\" type=\"search\" placeholder=\"Filter conflicts\" data-target=\"conflict-list\">\n<div id=\"conflict-list\">\n"
+    );
+    for conflict in &report.conflicts {
+        html.push_str(&conflict_card(conflict));
+    }
+    html.push_str("</div>");
+
+    html
+}
+
+/// A card describing a conflict, with the source of both routes side by side
+fn conflict_card(conflict: &RouteConflict) -> String {
+    let severity = conflict.severity.as_str();
+
+    let mut meta = vec![escape(&conflict.describe()), format!("Similarity {:.2}%", conflict.similarity)];
+    if let Some(url) = &conflict.sample_url {
+        meta.push(format!("Sample URL <code>{}</code>", escape(url)));
+    }
+
+    format!(
+        "<div class=\"card {severity}\" data-item>\n<h3><span class=\"badge {severity}\">{severity}</span> {}</h3>\n\
+         <div class=\"meta\">{}</div>\n<div class=\"sides\">\n{}{}</div>\n</div>\n",
+        escape(conflict.conflict_type.as_str()),
+        meta.join(" &middot; "),
+        route_side("Registered first", &conflict.route1),
+        route_side("Registered later", &conflict.route2),
+    )
+}
+
+fn route_side(label: &str, route: &Route) -> String {
+    let mut location = format!("{} &middot; {}:{}:{}", label, escape(&route.file_path), route.line_number, route.column);
+    if let Some(mounts) = route.mount_description() {
+        location.push_str(&format!(" &middot; mounted via <code>{}</code>", escape(&mounts)));
+    }
+    if let Some(middleware) = route.middleware_description() {
+        location.push_str(&format!(" &middot; middleware <code>{}</code>", escape(&middleware)));
+    }
+
+    format!(
+        "<div class=\"side\">\n<h4><span class=\"method\">{}</span> <code>{}</code></h4>\n<div class=\"location\">{}</div>\n{}</div>\n",
+        escape(&route.method), escape(&route.path), location, snippet(route)
+    )
+}
+
+/// The lines around a route's registration, with its own line highlighted
+fn snippet(route: &Route) -> String {
+    let Some(snippet) = &route.snippet else {
+        return "<p class=\"muted\">Source unavailable.</p>\n".to_string();
+    };
+
+    let mut html = String::from("<pre class=\"snippet\">");
+    for (number, line) in snippet.numbered_lines() {
+        let class = if number == route.line_number { " class=\"focus\"" } else { "" };
+        html.push_str(&format!(
+            "<span{}><span class=\"line-number\">{}</span>{}</span>",
+            class, number, escape(line)
+        ));
+    }
+    html.push_str("</pre>\n");
+
+    html
+}
+
+/// Suppressed conflicts, unused suppressions and stale baseline entries, when there are any
+fn notices(report: &AnalysisReport) -> String {
+    let mut items = Vec::new();
+
+    for entry in &report.suppressed_conflicts {
+        items.push(format!(
+            "<li><span class=\"badge {}\">{}</span> {} by <code>{}</code> at {}:{}</li>",
+            entry.conflict.severity, entry.conflict.severity, escape(&entry.conflict.describe()),
+            escape(&entry.suppression.directive()), escape(&entry.suppression.file_path), entry.suppression.line_number
+        ));
+    }
+    for suppression in &report.unused_suppressions {
+        items.push(format!(
+            "<li>Unused suppression <code>{}</code> at {}:{}</li>",
+            escape(&suppression.directive()), escape(&suppression.file_path), suppression.line_number
+        ));
+    }
+    if let Some(baseline) = &report.baseline {
+        for entry in &baseline.stale {
+            items.push(format!(
+                "<li>Stale baseline entry: {} between <code>{}</code> ({}) and <code>{}</code> ({})</li>",
+                escape(entry.conflict_type.as_str()), escape(&entry.route1), escape(&entry.file1),
+                escape(&entry.route2), escape(&entry.file2)
+            ));
+        }
+    }
+
+    if items.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        "<section id=\"suppressions\">\n<h2>Suppressions and Baseline</h2>\n<ul class=\"plain\">\n{}\n</ul>\n</section>",
+        items.join("\n")
+    )
+}
+
+/// A sortable, filterable table of every route
+fn routes_table(report: &AnalysisReport) -> String {
+    if report.routes.is_empty() {
+        return "<p class=\"muted\">No routes found.</p>".to_string();
+    }
+
+    let mut html = String::from(
+        "<input class=\"filter\" type=\"search\" placeholder=\"Filter routes\" data-target=\"route-table\">\n\
+         <table id=\"route-table\" class=\"sortable\">\n<thead><tr><th>#</th><th>Method</th><th>Path</th>\
+         <th>Router</th><th>File</th><th>Line</th><th>Mounted Via</th><th>Middleware</th><th>Conflicts</th></tr></thead>\n<tbody>\n"
+    );
+
+    let conflicts_by_route = report.conflicts_by_route();
+    for route in &report.routes {
+        let conflicts = conflicts_by_route.get(&route.identity()).map_or(0, Vec::len);
+
+        html.push_str(&format!(
+            "<tr data-item><td data-sort=\"{}\">{}</td><td class=\"method\">{}</td><td><code>{}</code></td>\
+             <td>{}</td><td>{}</td><td data-sort=\"{}\">{}:{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            route.order, route.order + 1,
+            escape(&route.method),
+            escape(&route.path),
+            escape(&route.router),
+            escape(&route.file_path),
+            route.line_number, route.line_number, route.column,
+            escape(&route.mount_description().unwrap_or_default()),
+            escape(&route.middleware_description().unwrap_or_default()),
+            conflicts
+        ));
+    }
+    html.push_str("</tbody>\n</table>");
+
+    html
+}
+
+/// Routes arranged by path segment in collapsible sections
+fn route_tree(report: &AnalysisReport) -> String {
+    if report.routes.is_empty() {
+        return "<p class=\"muted\">No routes found.</p>".to_string();
+    }
+
+    let mut root = TreeNode::default();
+    for route in &report.routes {
+        let mut node = &mut root;
+        for segment in route.path.split('/').filter(|segment| !segment.is_empty()) {
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        node.routes.push(route);
+    }
+
+    let mut html = String::from("<div class=\"tree\">\n");
+    render_tree_node("/", &root, 0, &mut html);
+    html.push_str("</div>");
+
+    html
+}
+
+fn render_tree_node(label: &str, node: &TreeNode, depth: usize, html: &mut String) {
+    let count = tree_route_count(node);
+    html.push_str(&format!(
+        "<details{}><summary><code>{}</code> <span class=\"count\">{} route{}</span></summary>\n",
+        if depth < 2 { " open" } else { "" },
+        escape(label),
+        count,
+        if count == 1 { "" } else { "s" }
+    ));
+
+    if !node.routes.is_empty() {
+        html.push_str("<ul>\n");
+        for route in &node.routes {
+            html.push_str(&format!(
+                "<li><span class=\"method\">{}</span> <code>{}</code> <span class=\"location\">{}:{}</span></li>\n",
+                escape(&route.method), escape(&route.path), escape(&route.file_path), route.line_number
+            ));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    for (segment, child) in &node.children {
+        render_tree_node(&format!("/{}", segment), child, depth + 1, html);
+    }
+    html.push_str("</details>\n");
+}
+
+fn tree_route_count(node: &TreeNode) -> usize {
+    node.routes.len() + node.children.values().map(tree_route_count).sum::<usize>()
+}
+
+/// A grid of the pairwise similarity of distinct route paths, shaded by score
+fn heatmap(report: &AnalysisReport) -> String {
+    let mut seen = HashSet::new();
+    let mut paths: Vec<&str> = report.routes
+        .iter()
+        .map(|route| route.path.as_str())
+        .filter(|path| seen.insert(*path))
+        .collect();
+
+    if paths.len() < 2 {
+        return "<p class=\"muted\">At least two distinct paths are needed for a heatmap.</p>".to_string();
+    }
+
+    let mut html = String::new();
+    if paths.len() > HEATMAP_LIMIT {
+        html.push_str(&format!(
+            "<p class=\"muted\">Showing the first {} of {} distinct paths.</p>\n",
+            HEATMAP_LIMIT, paths.len()
+        ));
+        paths.truncate(HEATMAP_LIMIT);
+    }
+
+    html.push_str("<div class=\"heatmap\">\n<table>\n<tr><th></th>");
+    for (index, path) in paths.iter().enumerate() {
+        html.push_str(&format!("<th title=\"{}\">{}</th>", escape(path), index + 1));
+    }
+    html.push_str("</tr>\n");
+
+    for (row, path1) in paths.iter().enumerate() {
+        html.push_str(&format!("<tr><th class=\"row\">{}. <code>{}</code></th>", row + 1, escape(path1)));
+        for (column, path2) in paths.iter().enumerate() {
+            if row == column {
+                html.push_str("<td class=\"cell muted\">&mdash;</td>");
+                continue;
+            }
+
+            let similarity = path_similarity(path1, path2, report.similarity_algorithm);
+            html.push_str(&format!(
+                "<td class=\"cell\" style=\"background: rgba(207, 34, 46, {:.2})\" title=\"{} &harr; {}: {:.2}%\">{:.0}</td>",
+                similarity / 100.0, escape(path1), escape(path2), similarity, similarity
+            ));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</div>");
+
+    html
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,635 @@
+use std::collections::{HashMap, HashSet};
+use oxc_allocator::Allocator;
+use oxc_ast::Comment;
+use oxc_ast::ast::{
+    Argument, BindingPattern, CallExpression, ExportDefaultDeclaration,
+    ExportDefaultDeclarationKind, Expression, IfStatement, ImportDeclaration,
+    ImportDeclarationSpecifier, Statement, VariableDeclarator, AssignmentExpression,
+    AssignmentTarget, PropertyKey,
+};
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, SourceType};
+use serde::{Serialize, Deserialize};
+use crate::models::SuppressionKind;
+
+/// Every method Express exposes as a routing function (from the `methods` package), plus `all`
+const ROUTE_METHODS: [&str; 26] = [
+    "get", "post", "put", "head", "delete", "options", "trace", "copy", "lock", "mkcol",
+    "move", "purge", "propfind", "proppatch", "unlock", "report", "mkactivity", "checkout",
+    "merge", "notify", "subscribe", "unsubscribe", "patch", "search", "connect", "all",
+];
+/// Names assumed to hold an app or router even when their creation isn't visible,
+/// e.g. when a router is passed into a function as a parameter
+const ROUTER_RECEIVERS: [&str; 2] = ["app", "router"];
+
+/// A route registration such as `router.get('/users', handler)`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RouteCall {
+    pub receiver: String,
+    pub method: String,
+    pub path: String,
+    pub line_number: usize,
+    pub column: usize,
+    /// Names of the handler arguments before the last one, e.g. `auth` in `get('/x', auth, show)`
+    pub middleware: Vec<String>,
+}
+
+/// A `receiver.use(...)` call
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UseCall {
+    pub receiver: String,
+    pub prefix: String,
+    /// The arguments after the prefix, in order
+    pub arguments: Vec<UseArgument>,
+    pub line_number: usize,
+    pub column: usize,
+}
+
+/// A `use()` argument: a middleware function or a router
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UseArgument {
+    /// How the argument is written, e.g. `auth` or `express.json()`
+    pub name: String,
+    /// What the argument refers to, when it may be a router
+    pub target: Option<UseTarget>,
+}
+
+/// A `use()` argument that may refer to a router
+#[derive(Clone, Serialize, Deserialize)]
+pub enum UseTarget {
+    Binding(String),
+    Require(String),
+}
+
+/// A suppression comment and the lines whose routes it covers
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SuppressionComment {
+    pub kind: SuppressionKind,
+    pub rules: Vec<String>,
+    pub line_number: usize,
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// Route-relevant facts found in a single JavaScript or TypeScript module
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct ModuleScan {
+    pub routes: Vec<RouteCall>,
+    pub uses: Vec<UseCall>,
+    /// Bindings holding an Express application or router instance
+    pub routers: HashSet<String>,
+    /// Local bindings initialized from a module specifier, e.g. `const users = require('./users')`
+    pub imports: HashMap<String, String>,
+    /// Binding exported as the module's default export
+    pub export: Option<String>,
+    pub suppressions: Vec<SuppressionComment>,
+}
+
+/// Parse a module's source and collect the route registrations, mounts and module bindings in it.
+///
+/// Commented-out code is never seen by the parser, and branches that can never run
+/// (`if (false) { ... }`, statements after a `return`) are skipped.
+pub fn scan_module(file_path: &str, source: &str) -> ModuleScan {
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(file_path).unwrap_or_default();
+
+    let mut parsed = Parser::new(&allocator, source, source_type).parse();
+    if parsed.panicked && source_type.is_module() {
+        // CommonJS files occasionally use syntax that is only valid in scripts
+        parsed = Parser::new(&allocator, source, source_type.with_script(true)).parse();
+    }
+
+    let mut scanner = ModuleScanner {
+        line_index: LineIndex::new(source),
+        scan: ModuleScan::default(),
+        express_bindings: HashSet::from(["express".to_string()]),
+        router_factories: HashSet::from(["Router".to_string()]),
+    };
+    scanner.visit_program(&parsed.program);
+    scanner.scan.suppressions = suppression_comments(&parsed.program.comments, source, &scanner.line_index);
+
+    // Keep only calls made on something that can be a router: a local instance, a
+    // conventionally named binding, or a binding imported from another module
+    let mut scan = scanner.scan;
+    let routes = std::mem::take(&mut scan.routes);
+    scan.routes = routes.into_iter().filter(|call| scan.is_receiver(&call.receiver)).collect();
+    let uses = std::mem::take(&mut scan.uses);
+    scan.uses = uses.into_iter().filter(|call| scan.is_receiver(&call.receiver)).collect();
+
+    let conventional: Vec<String> = scan.routes.iter().map(|call| &call.receiver)
+        .chain(scan.uses.iter().map(|call| &call.receiver))
+        .filter(|name| ROUTER_RECEIVERS.contains(&name.as_str()) && !scan.imports.contains_key(*name))
+        .cloned()
+        .collect();
+    scan.routers.extend(conventional);
+
+    scan
+}
+
+impl ModuleScan {
+    fn is_receiver(&self, name: &str) -> bool {
+        self.routers.contains(name) || self.imports.contains_key(name) || ROUTER_RECEIVERS.contains(&name)
+    }
+}
+
+struct ModuleScanner<'s> {
+    line_index: LineIndex<'s>,
+    scan: ModuleScan,
+    /// Bindings referring to the `express` module itself (`const express = require('express')`)
+    express_bindings: HashSet<String>,
+    /// Bindings referring to `express.Router` (`const { Router } = require('express')`)
+    router_factories: HashSet<String>,
+}
+
+impl<'a> Visit<'a> for ModuleScanner<'_> {
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if let Some((object, method)) = member_call(&it.callee) {
+            if let Some(receiver) = router_receiver(object) {
+                // With a single argument, `app.get('name')` reads a setting instead of routing
+                if ROUTE_METHODS.contains(&method) && it.arguments.len() >= 2 {
+                    if let Some(first) = it.arguments.first() {
+                        let middleware = self.middleware_names(&it.arguments[1..]);
+                        self.record_route(receiver, method, route_paths(first), middleware, it.span.start);
+                    }
+                } else if method == "use" {
+                    self.record_use(receiver, it);
+                }
+            } else if ROUTE_METHODS.contains(&method) {
+                // `app.route('/book').get(...).post(...)`
+                if let Some((receiver, paths)) = route_chain(object) {
+                    // The callee holds the earlier calls of the chain, so record them first
+                    walk::walk_call_expression(self, it);
+                    let middleware = self.middleware_names(&it.arguments);
+                    self.record_route(receiver, method, paths, middleware, method_offset(&it.callee));
+                    return;
+                }
+            }
+        }
+
+        walk::walk_call_expression(self, it);
+    }
+
+    fn visit_variable_declarator(&mut self, it: &VariableDeclarator<'a>) {
+        if let Some(init) = &it.init {
+            match &it.id {
+                BindingPattern::BindingIdentifier(ident) => self.record_binding(&ident.name, init),
+                BindingPattern::ObjectPattern(pattern) if is_express_require(init) => {
+                    for property in &pattern.properties {
+                        let (PropertyKey::StaticIdentifier(key), Some(local)) =
+                            (&property.key, property.value.get_binding_identifier())
+                        else {
+                            continue;
+                        };
+                        if key.name == "Router" {
+                            self.router_factories.insert(local.name.to_string());
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        walk::walk_variable_declarator(self, it);
+    }
+
+    fn visit_import_declaration(&mut self, it: &ImportDeclaration<'a>) {
+        let from_express = it.source.value == "express";
+
+        for specifier in it.specifiers.iter().flatten() {
+            match specifier {
+                ImportDeclarationSpecifier::ImportDefaultSpecifier(s) if from_express => {
+                    self.express_bindings.insert(s.local.name.to_string());
+                },
+                ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) if from_express => {
+                    self.express_bindings.insert(s.local.name.to_string());
+                },
+                ImportDeclarationSpecifier::ImportSpecifier(s) if from_express => {
+                    if s.imported.name() == "Router" {
+                        self.router_factories.insert(s.local.name.to_string());
+                    }
+                },
+                ImportDeclarationSpecifier::ImportDefaultSpecifier(s) => {
+                    self.scan.imports.insert(s.local.name.to_string(), it.source.value.to_string());
+                },
+                ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) => {
+                    self.scan.imports.insert(s.local.name.to_string(), it.source.value.to_string());
+                },
+                ImportDeclarationSpecifier::ImportSpecifier(_) => {},
+            }
+        }
+    }
+
+    fn visit_export_default_declaration(&mut self, it: &ExportDefaultDeclaration<'a>) {
+        if let ExportDefaultDeclarationKind::Identifier(ident) = &it.declaration {
+            self.scan.export = Some(ident.name.to_string());
+        }
+
+        walk::walk_export_default_declaration(self, it);
+    }
+
+    fn visit_assignment_expression(&mut self, it: &AssignmentExpression<'a>) {
+        match &it.left {
+            AssignmentTarget::StaticMemberExpression(member)
+                if member.object.is_specific_id("module") && member.property.name == "exports" =>
+            {
+                if let Expression::Identifier(ident) = it.right.get_inner_expression() {
+                    self.scan.export = Some(ident.name.to_string());
+                }
+            },
+            AssignmentTarget::AssignmentTargetIdentifier(ident) => self.record_binding(&ident.name, &it.right),
+            _ => {},
+        }
+
+        walk::walk_assignment_expression(self, it);
+    }
+
+    fn visit_if_statement(&mut self, it: &IfStatement<'a>) {
+        match constant_truthiness(&it.test) {
+            Some(true) => self.visit_statement(&it.consequent),
+            Some(false) => {
+                if let Some(alternate) = &it.alternate {
+                    self.visit_statement(alternate);
+                }
+            },
+            None => walk::walk_if_statement(self, it),
+        }
+    }
+
+    fn visit_statements(&mut self, it: &oxc_allocator::Vec<'a, Statement<'a>>) {
+        let mut reachable = true;
+        for statement in it {
+            // Function declarations are hoisted, so they can be called even when they follow
+            // a statement that leaves the block
+            if reachable || matches!(statement, Statement::FunctionDeclaration(_)) {
+                self.visit_statement(statement);
+            }
+            if matches!(
+                statement,
+                Statement::ReturnStatement(_)
+                    | Statement::ThrowStatement(_)
+                    | Statement::BreakStatement(_)
+                    | Statement::ContinueStatement(_)
+            ) {
+                reachable = false;
+            }
+        }
+    }
+}
+
+impl ModuleScanner<'_> {
+    /// Remember what a binding was initialized with, if it matters for routing
+    fn record_binding(&mut self, name: &str, init: &Expression) {
+        if is_express_require(init) {
+            self.express_bindings.insert(name.to_string());
+        } else if let Some(specifier) = require_specifier(init) {
+            self.scan.imports.insert(name.to_string(), specifier);
+        } else if self.creates_router(init) {
+            self.scan.routers.insert(name.to_string());
+        }
+    }
+
+    /// Whether an expression creates an app or router: `express()`, `express.Router()`,
+    /// `Router()`, `new Router()` or `require('express').Router()`
+    fn creates_router(&self, expr: &Expression) -> bool {
+        let callee = match expr.get_inner_expression() {
+            Expression::CallExpression(call) => &call.callee,
+            Expression::NewExpression(new) => &new.callee,
+            _ => return false,
+        };
+
+        match callee.get_inner_expression() {
+            Expression::Identifier(ident) => {
+                self.express_bindings.contains(ident.name.as_str())
+                    || self.router_factories.contains(ident.name.as_str())
+            },
+            Expression::StaticMemberExpression(member) if member.property.name == "Router" => {
+                match member.object.get_inner_expression() {
+                    Expression::Identifier(ident) => self.express_bindings.contains(ident.name.as_str()),
+                    object => is_express_require(object),
+                }
+            },
+            other => is_express_require(other),
+        }
+    }
+
+    fn record_route(&mut self, receiver: &str, method: &str, paths: Vec<String>, middleware: Vec<String>, offset: u32) {
+        let (line_number, column) = self.line_index.position(offset);
+
+        for path in paths {
+            self.scan.routes.push(RouteCall {
+                receiver: receiver.to_string(),
+                method: method.to_uppercase(),
+                path,
+                line_number,
+                column,
+                middleware: middleware.clone(),
+            });
+        }
+    }
+
+    /// Names of a route's handler arguments except the last, which handles the request
+    fn middleware_names(&self, handlers: &[Argument]) -> Vec<String> {
+        let mut names: Vec<String> = handler_expressions(handlers)
+            .into_iter()
+            .map(|expr| self.handler_name(expr))
+            .collect();
+        names.pop();
+        names
+    }
+
+    /// A short name for a handler argument: its identifier, member path or callee, e.g.
+    /// `auth`, `auth.required` or `passport.authenticate()`
+    fn handler_name(&self, expr: &Expression) -> String {
+        match expr.get_inner_expression() {
+            Expression::Identifier(ident) => ident.name.to_string(),
+            Expression::CallExpression(call) => format!("{}()", self.handler_name(&call.callee)),
+            Expression::FunctionExpression(function) => function.id
+                .as_ref()
+                .map(|id| id.name.to_string())
+                .unwrap_or_else(|| "<anonymous>".to_string()),
+            Expression::ArrowFunctionExpression(_) => "<anonymous>".to_string(),
+            other => {
+                let span = other.span();
+                let text = &self.line_index.source[span.start as usize..span.end as usize];
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            },
+        }
+    }
+
+    fn record_use(&mut self, receiver: &str, call: &CallExpression) {
+        let mut args = call.arguments.iter().peekable();
+        let prefix = match args.peek().and_then(|arg| static_string(arg.as_expression()?)) {
+            Some(prefix) => {
+                args.next();
+                prefix
+            },
+            None => "/".to_string(),
+        };
+
+        let rest: Vec<&Argument> = args.collect();
+        let arguments = handler_expressions(rest)
+            .into_iter()
+            .map(|expr| {
+                let target = match expr.get_inner_expression() {
+                    Expression::Identifier(ident) => Some(UseTarget::Binding(ident.name.to_string())),
+                    other => require_specifier(other).map(UseTarget::Require),
+                };
+                UseArgument { name: self.handler_name(expr), target }
+            })
+            .collect();
+
+        let (line_number, column) = self.line_index.position(call.span.start);
+        self.scan.uses.push(UseCall {
+            receiver: receiver.to_string(),
+            prefix,
+            arguments,
+            line_number,
+            column,
+        });
+    }
+}
+
+/// Split a `object.method(...)` callee into its object and method name
+fn member_call<'e, 'a>(callee: &'e Expression<'a>) -> Option<(&'e Expression<'a>, &'e str)> {
+    let Expression::StaticMemberExpression(member) = callee.get_inner_expression() else {
+        return None;
+    };
+
+    Some((member.object.get_inner_expression(), member.property.name.as_str()))
+}
+
+/// Where the method name of a member call starts, so chained calls point at their own line
+fn method_offset(callee: &Expression) -> u32 {
+    match callee.get_inner_expression() {
+        Expression::StaticMemberExpression(member) => member.property.span.start,
+        other => other.span().start,
+    }
+}
+
+/// The binding a method is called on, when it is a plain identifier
+fn router_receiver<'e>(expr: &'e Expression) -> Option<&'e str> {
+    match expr {
+        Expression::Identifier(ident) => Some(ident.name.as_str()),
+        _ => None,
+    }
+}
+
+/// Follow a chain like `app.route('/book').get(...).post(...)` back to the `route()` call
+fn route_chain<'e>(expr: &'e Expression) -> Option<(&'e str, Vec<String>)> {
+    let Expression::CallExpression(call) = expr else {
+        return None;
+    };
+    let (object, method) = member_call(&call.callee)?;
+
+    if method == "route" {
+        let receiver = router_receiver(object)?;
+        Some((receiver, route_paths(call.arguments.first()?)))
+    } else if ROUTE_METHODS.contains(&method) {
+        route_chain(object)
+    } else {
+        None
+    }
+}
+
+/// Handler arguments in the order Express runs them; arrays of handlers are flattened
+fn handler_expressions<'e, 'a: 'e>(arguments: impl IntoIterator<Item = &'e Argument<'a>>) -> Vec<&'e Expression<'a>> {
+    let mut handlers = Vec::new();
+    for expr in arguments.into_iter().filter_map(|argument| argument.as_expression()) {
+        match expr.get_inner_expression() {
+            Expression::ArrayExpression(array) => {
+                handlers.extend(array.elements.iter().filter_map(|element| element.as_expression()));
+            },
+            _ => handlers.push(expr),
+        }
+    }
+    handlers
+}
+
+/// Paths registered by a route call's first argument; arrays register every element
+fn route_paths(argument: &Argument) -> Vec<String> {
+    let Some(expr) = argument.as_expression() else {
+        return Vec::new();
+    };
+
+    match expr.get_inner_expression() {
+        Expression::ArrayExpression(array) => array
+            .elements
+            .iter()
+            .filter_map(|element| static_string(element.as_expression()?))
+            .collect(),
+        other => static_string(other).into_iter().collect(),
+    }
+}
+
+/// The value of a string literal or a template literal without substitutions
+fn static_string(expr: &Expression) -> Option<String> {
+    match expr.get_inner_expression() {
+        Expression::StringLiteral(literal) => Some(literal.value.to_string()),
+        Expression::TemplateLiteral(template) if template.expressions.is_empty() => template
+            .quasis
+            .first()
+            .and_then(|quasi| quasi.value.cooked.as_ref())
+            .map(|cooked| cooked.to_string()),
+        _ => None,
+    }
+}
+
+/// The module specifier of a `require('...')` call
+fn require_specifier(expr: &Expression) -> Option<String> {
+    let Expression::CallExpression(call) = expr.get_inner_expression() else {
+        return None;
+    };
+    if !call.callee.is_specific_id("require") || call.arguments.len() != 1 {
+        return None;
+    }
+
+    static_string(call.arguments[0].as_expression()?)
+}
+
+/// Whether an expression is `require('express')`
+fn is_express_require(expr: &Expression) -> bool {
+    require_specifier(expr).is_some_and(|specifier| specifier == "express")
+}
+
+/// Whether an `if` test is a literal that always (or never) passes
+fn constant_truthiness(expr: &Expression) -> Option<bool> {
+    match expr.get_inner_expression() {
+        Expression::BooleanLiteral(literal) => Some(literal.value),
+        Expression::NumericLiteral(literal) => Some(literal.value != 0.0 && !literal.value.is_nan()),
+        Expression::NullLiteral(_) => Some(false),
+        Expression::Identifier(ident) if ident.name == "undefined" => Some(false),
+        _ => None,
+    }
+}
+
+/// Collect `insidious-ignore-next-line` and `insidious-disable` comments, closing each
+/// `insidious-disable` at the next `insidious-enable` or the end of the file
+fn suppression_comments(comments: &[Comment], source: &str, line_index: &LineIndex) -> Vec<SuppressionComment> {
+    let mut suppressions: Vec<SuppressionComment> = Vec::new();
+    let mut open_disables = Vec::new();
+
+    for comment in comments {
+        let span = comment.content_span();
+        let Some((directive, rules)) = parse_directive(&source[span.start as usize..span.end as usize]) else {
+            continue;
+        };
+        let (line_number, _) = line_index.position(comment.span.start);
+        let (end_line, _) = line_index.position(comment.span.end);
+
+        let kind = match directive {
+            "insidious-ignore-next-line" => SuppressionKind::IgnoreNextLine,
+            "insidious-disable" => SuppressionKind::Disable,
+            _ => {
+                // A bare enable closes every open disable, one naming rules only the disables
+                // limited to those rules
+                open_disables.retain(|&index| {
+                    let disable: &mut SuppressionComment = &mut suppressions[index];
+                    let closes = rules.is_empty()
+                        || (!disable.rules.is_empty() && disable.rules.iter().all(|rule| rules.contains(rule)));
+                    if closes {
+                        disable.last_line = line_number.saturating_sub(1).max(disable.first_line);
+                    }
+                    !closes
+                });
+                continue;
+            },
+        };
+
+        let (first_line, last_line) = match kind {
+            SuppressionKind::IgnoreNextLine => (end_line + 1, end_line + 1),
+            SuppressionKind::Disable => {
+                open_disables.push(suppressions.len());
+                (line_number, usize::MAX)
+            },
+        };
+        suppressions.push(SuppressionComment { kind, rules, line_number, first_line, last_line });
+    }
+
+    suppressions
+}
+
+/// Split a comment into its directive and the conflict types it names, ignoring
+/// block comment decoration and an explanation after `--`
+fn parse_directive(text: &str) -> Option<(&'static str, Vec<String>)> {
+    let text = text.trim().trim_start_matches('*').trim();
+    let text = text.split_once("--").map_or(text, |(directive, _reason)| directive);
+    let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+
+    let directive = ["insidious-ignore-next-line", "insidious-disable", "insidious-enable"]
+        .into_iter()
+        .find(|directive| *directive == name)?;
+    let rules = rest
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|rule| !rule.is_empty())
+        .map(|rule| rule.to_lowercase())
+        .collect();
+
+    Some((directive, rules))
+}
+
+/// Maps byte offsets to 1-based line and column numbers
+struct LineIndex<'s> {
+    source: &'s str,
+    line_starts: Vec<usize>,
+}
+
+impl<'s> LineIndex<'s> {
+    fn new(source: &'s str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex { source, line_starts }
+    }
+
+    fn position(&self, offset: u32) -> (usize, usize) {
+        let offset = offset as usize;
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.source[self.line_starts[line]..offset].chars().count() + 1;
+
+        (line + 1, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn methods(scan: &ModuleScan) -> Vec<&str> {
+        scan.routes.iter().map(|call| call.method.as_str()).collect()
+    }
+
+    #[test]
+    fn route_chains_are_recorded_in_source_order() {
+        let scan = scan_module("app.js", "app.route('/x').all(h).get(h2);\napp.route('/book').get(a).post(b).put(c);");
+
+        assert_eq!(methods(&scan), ["ALL", "GET", "GET", "POST", "PUT"]);
+    }
+
+    fn paths(scan: &ModuleScan) -> Vec<&str> {
+        scan.routes.iter().map(|call| call.path.as_str()).collect()
+    }
+
+    #[test]
+    fn hoisted_functions_after_return_are_scanned() {
+        let scan = scan_module(
+            "app.js",
+            "module.exports = function register(app) {\n  return setup();\n  app.get('/dead', h);\n  function setup() { app.get('/hoisted', h); }\n};",
+        );
+
+        assert_eq!(paths(&scan), ["/hoisted"]);
+    }
+
+    #[test]
+    fn enable_with_rules_closes_only_matching_disables() {
+        let scan = scan_module(
+            "app.js",
+            "// insidious-disable\n// insidious-disable shadowed-route\n// insidious-disable exact-match\n\
+             // insidious-enable shadowed-route\napp.get('/a', h);\n// insidious-enable\napp.get('/b', h);",
+        );
+        let last_lines: Vec<usize> = scan.suppressions.iter().map(|comment| comment.last_line).collect();
+
+        assert_eq!(last_lines, [5, 3, 5]);
+    }
+}
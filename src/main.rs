@@ -1,10 +1,31 @@
 mod cli;
+mod js_parser;
 mod models;
 mod route_extractor;
+mod route_index;
+mod route_pattern;
+mod similarity;
 mod conflict_analyzer;
 mod report;
+mod html_report;
 mod utils;
 mod config;
+mod baseline;
+mod scan_cache;
+mod watch;
+mod git_tree;
+mod route_diff;
+mod report_compare;
+mod openapi;
+mod spec_check;
+mod output;
+
+use std::path::Path;
+use std::process;
+use cli::AnalysisConfig;
+use models::{AnalysisReport, Route, RouteConflict};
+use output::SectionedOutput;
+use route_extractor::Extraction;
 
 fn main() {
     let args = cli::get_args();
@@ -13,7 +34,69 @@ fn main() {
         cli::Commands::Init => {
             // Initialize configuration file
             config::init_config();
-            println!("Configuration file created: .express-analyzer.json");
+            println!("Configuration file created: {}", config::CONFIG_FILE_NAME);
+        },
+
+        cli::Commands::Config {
+            action: cli::ConfigCommand::Show { project_directory, options },
+        } => {
+            let resolved = match cli::resolve_config(&args, project_directory, options) {
+                Ok(resolved) => resolved,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            config::print_resolved_config(project_directory, &resolved);
+        },
+
+        cli::Commands::Cache {
+            action: cli::CacheCommand::Clear { project_directory },
+        } => {
+            let path = scan_cache::ScanCache::path(project_directory);
+            match scan_cache::clear(project_directory) {
+                Ok(true) => println!("Removed cache file {}", path.display()),
+                Ok(false) => println!("No cache file found at {}", path.display()),
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            }
+        },
+
+        cli::Commands::Baseline {
+            action: cli::BaselineCommand::Create { project_directory, output, .. },
+        } => {
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            println!("Recording route conflicts in: {}", project_directory);
+
+            let extraction = route_extractor::extract_all_routes(&config);
+            let analysis = conflict_analyzer::check_route_conflicts(&extraction.routes, &extraction.suppressions, &config);
+            let baseline = baseline::Baseline::from_conflicts(&analysis.conflicts, project_directory);
+
+            let path = match output {
+                Some(path) => Path::new(path).to_path_buf(),
+                None => Path::new(project_directory).join(baseline::BASELINE_FILE_NAME),
+            };
+            if let Err(err) = baseline.save(&path) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            println!("Baseline of {} conflict(s) saved to {}", baseline.entries.len(), path.display());
         },
         
         cli::Commands::Report { 
@@ -22,70 +105,366 @@ fn main() {
             output,
             .. // We don't need to extract the other parameters here as they're handled in get_analysis_config
         } => {
+            let targets = match cli::report_targets(format, output) {
+                Ok(targets) => targets,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            // Keep standard output for the report itself when it is written there
+            let to_stdout = targets.iter().any(|(_, output)| *output == Some("-"));
+            let status = |message: String| print_status(&message, to_stdout);
+
             // Validate project directory
             if let Err(err) = cli::validate_project_path(project_directory) {
-                println!("{}", err);
-                return;
+                status(err);
+                process::exit(1);
             }
             
             // Get analysis configuration
-            let config = cli::get_analysis_config(&args);
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    status(err);
+                    process::exit(1);
+                }
+            };
             
-            println!("Analyzing Express.js routes in: {}", project_directory);
-            println!("Generating {} report...", format);
+            let names: Vec<&str> = format.iter().map(|format| format.as_str()).collect();
+            status(format!("Analyzing Express.js routes in: {}", project_directory));
+            status(format!("Generating {} report...", names.join(", ")));
             
             // Extract routes and analyze conflicts
-            let routes = route_extractor::extract_all_routes(&config);
-            let conflicts = conflict_analyzer::check_route_conflicts(&routes, &config);
-            let report = report::create_analysis_report(routes, conflicts);
+            let report = match analyze_project(&config) {
+                Ok(report) => report,
+                Err(err) => {
+                    status(err);
+                    process::exit(1);
+                }
+            };
             
-            // Generate report in the specified format
-            match format.as_str() {
-                "json" => report::save_json_report(&report, output.as_deref()),
-                "html" => report::save_html_report(&report, output.as_deref()),
-                "markdown" => report::save_markdown_report(&report, output.as_deref()),
-                _ => {
-                    println!("Unsupported format: {}. Using JSON format instead.", format);
-                    report::save_json_report(&report, output.as_deref());
+            // Generate a report in each requested format
+            let registry = report::FormatterRegistry::default();
+            for (format, output) in targets {
+                let written = registry.get(format)
+                    .and_then(|formatter| {
+                        report::write_report(&report, formatter, output)
+                            .map(|path| path.map(|path| (formatter.display_name(), path)))
+                    });
+
+                match written {
+                    Ok(Some((name, path))) => status(format!("Detailed {} report saved to {}", name, path.display())),
+                    Ok(None) => {},
+                    Err(err) => {
+                        status(err);
+                        process::exit(1);
+                    }
                 }
             }
             
-            println!("Report generation complete.");
+            status("Report generation complete.".to_string());
         },
         
-        cli::Commands::Analyze { 
+        cli::Commands::Check {
             project_directory,
-            exclude,
-            extensions,
-            similarity_threshold
+            fail_on,
+            ..
         } => {
+            // Validate project directory
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            println!("Checking Express.js routes in: {}", project_directory);
+
+            let report = match analyze_project(&config) {
+                Ok(report) => report,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            report::print_report_summary(&report);
+
+            // Only findings at or above the requested severity fail the check
+            let failing: Vec<_> = report.conflicts
+                .iter()
+                .filter(|conflict| conflict.severity >= *fail_on)
+                .collect();
+
+            match failing.iter().map(|conflict| conflict.severity).max() {
+                Some(highest) => {
+                    println!("\nCheck failed: {} finding(s) at or above {} severity", failing.len(), fail_on);
+                    process::exit(highest.exit_code());
+                },
+                None => println!("\nCheck passed: no findings at or above {} severity", fail_on),
+            }
+        },
+
+        cli::Commands::Watch { project_directory, .. } => {
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            if let Err(err) = watch::watch(&config, analyze_extraction) {
+                println!("{}", err);
+                process::exit(1);
+            }
+        },
+
+        cli::Commands::Export {
+            action: cli::ExportCommand::OpenApi { project_directory, output, .. },
+        } => {
+            // Keep standard output for the document itself when it is written there
+            let status = |message: String| print_status(&message, output == "-");
+
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                status(err);
+                process::exit(1);
+            }
+
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    status(err);
+                    process::exit(1);
+                }
+            };
+
+            let extraction = route_extractor::extract_all_routes(&config);
+            let (document, unsupported) = openapi::OpenApiDocument::new(&extraction.routes, &config);
+            for route in unsupported {
+                status(format!(
+                    "Skipped {} {} ({}:{}): OpenAPI 3.1 has no operation for this method",
+                    route.method, route.path, route.file_path, route.line_number
+                ));
+            }
+
+            let json = match serde_json::to_string_pretty(&document) {
+                Ok(json) => json + "\n",
+                Err(err) => {
+                    status(format!("Error: Could not serialize the OpenAPI document: {}", err));
+                    process::exit(1);
+                }
+            };
+            if output == "-" {
+                print!("{}", json);
+            } else {
+                if let Err(err) = std::fs::write(output, json) {
+                    status(format!("Error: Could not write '{}': {}", output, err));
+                    process::exit(1);
+                }
+                status(format!(
+                    "OpenAPI document with {} operation(s) on {} path(s) saved to {}",
+                    document.operation_count(), document.paths.len(), output
+                ));
+            }
+        },
+
+        cli::Commands::SpecCheck { project_directory, spec, base_path, format, .. } => {
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            let drift = cli::get_analysis_config(&args).and_then(|config| {
+                let loaded = spec_check::Spec::load(Path::new(spec), base_path.as_deref())?;
+                let extraction = route_extractor::extract_all_routes(&config);
+                Ok(spec_check::SpecDrift::new(spec, &loaded, &extraction.routes, &config))
+            });
+            let drift = match drift {
+                Ok(drift) => drift,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            match drift.render(*format) {
+                Ok(rendered) => print!("{}", rendered),
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            }
+            if drift.has_drift() {
+                process::exit(spec_check::DRIFT_EXIT_CODE);
+            }
+        },
+
+        cli::Commands::Compare { old, new, format, output } => {
+            let comparison = report_compare::SavedReport::load(Path::new(old)).and_then(|old_report| {
+                let new_report = report_compare::SavedReport::load(Path::new(new))?;
+                Ok(report_compare::ReportComparison::new(old, &old_report, new, &new_report))
+            });
+            let comparison = match comparison {
+                Ok(comparison) => comparison,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+
+            let rendered = match comparison.render(*format) {
+                Ok(rendered) => rendered,
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            };
+            match output {
+                Some(path) => {
+                    if let Err(err) = std::fs::write(path, rendered) {
+                        println!("Error: Could not write '{}': {}", path, err);
+                        process::exit(1);
+                    }
+                    println!("Comparison saved to {}", path);
+                },
+                None => print!("{}", rendered),
+            }
+        },
+
+        cli::Commands::Diff { project_directory, base, head, format, .. } => {
+            if let Err(err) = cli::validate_project_path(project_directory) {
+                println!("{}", err);
+                process::exit(1);
+            }
+
+            let rendered = cli::get_analysis_config(&args)
+                .and_then(|config| diff_revisions(&config, base, head))
+                .and_then(|diff| diff.render(*format));
+            match rendered {
+                Ok(rendered) => print!("{}", rendered),
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            }
+        },
+
+        cli::Commands::Analyze { project_directory, .. } => {
             // Validate project directory
             if let Err(err) = cli::validate_project_path(project_directory) {
                 println!("{}", err);
                 return;
             }
             
-            println!("Analyzing Express.js routes in: {}", project_directory);
-            println!("Excluded directories: {:?}", exclude);
-            println!("File extensions: {:?}", extensions);
-            println!("Similarity threshold: {}%", similarity_threshold);
-            
             // Get analysis configuration
-            let config = cli::get_analysis_config(&args);
+            let config = match cli::get_analysis_config(&args) {
+                Ok(config) => config,
+                Err(err) => {
+                    println!("{}", err);
+                    return;
+                }
+            };
             
-            // Extract routes and analyze conflicts
-            let routes = route_extractor::extract_all_routes(&config);
-            println!("Found {} routes in project", routes.len());
+            println!("Analyzing Express.js routes in: {}", project_directory);
+            println!("Excluded directories: {:?}", config.excluded_dirs);
+            println!("File extensions: {:?}", config.file_extensions);
+            println!("Similarity threshold: {}%", config.similarity_threshold);
             
-            let conflicts = conflict_analyzer::check_route_conflicts(&routes, &config);
-            let report = report::create_analysis_report(routes, conflicts);
+            // Extract routes and analyze conflicts
+            let report = match analyze_project(&config) {
+                Ok(report) => report,
+                Err(err) => {
+                    println!("{}", err);
+                    return;
+                }
+            };
+            println!("Found {} routes in project", report.total_routes);
             
             // Print summary report
             report::print_report_summary(&report);
             
             // Save detailed JSON report
-            report::save_json_report(&report, None);
-            println!("Detailed report saved to route_analysis_report.json");
+            let registry = report::FormatterRegistry::default();
+            let written = registry.get(report::ReportFormat::Json)
+                .and_then(|formatter| report::write_report(&report, formatter, None));
+            match written {
+                Ok(Some(path)) => println!("\nDetailed report saved to {}", path.display()),
+                Ok(None) => {},
+                Err(err) => {
+                    println!("{}", err);
+                    process::exit(1);
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Print a progress message, on standard error when standard output carries the command's result
+fn print_status(message: &str, stdout_is_output: bool) {
+    if stdout_is_output {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Scan the configured project and analyze every route it registers
+fn analyze_project(config: &AnalysisConfig) -> Result<AnalysisReport, String> {
+    analyze_extraction(route_extractor::extract_all_routes(config), config)
+}
+
+/// Find the conflicts between extracted routes, leaving out those suppressed in the source
+/// or accepted by the baseline
+fn analyze_extraction(extraction: Extraction, config: &AnalysisConfig) -> Result<AnalysisReport, String> {
+    let mut analysis = conflict_analyzer::check_route_conflicts(&extraction.routes, &extraction.suppressions, config);
+
+    let baseline_status = match &config.baseline {
+        Some(path) => {
+            let baseline = baseline::Baseline::load(path)?;
+            let (conflicts, status) = baseline.apply(analysis.conflicts, &config.project_directory, path);
+            analysis.conflicts = conflicts;
+            Some(status)
+        },
+        None => None,
+    };
+
+    let mut report = report::create_analysis_report(extraction.routes, analysis, config);
+    report.baseline = baseline_status;
+
+    Ok(report)
+}
+
+/// Compare the routes and conflicts of two git revisions of the project
+fn diff_revisions(config: &AnalysisConfig, base: &str, head: &str) -> Result<route_diff::RouteDiff, String> {
+    let analyze = |revision: &str| -> Result<(Vec<Route>, Vec<RouteConflict>), String> {
+        let tree = git_tree::GitTree::load(config, revision)?;
+        let extraction = route_extractor::ProjectScan::from_tree(&tree, config).extraction();
+        let analysis = conflict_analyzer::check_route_conflicts(&extraction.routes, &extraction.suppressions, config);
+        Ok((extraction.routes, analysis.conflicts))
+    };
+
+    let (base_routes, base_conflicts) = analyze(base)?;
+    let (head_routes, head_conflicts) = analyze(head)?;
+
+    Ok(route_diff::RouteDiff::new(
+        base,
+        head,
+        (&base_routes, &base_conflicts),
+        (&head_routes, &head_conflicts),
+        &config.project_directory,
+    ))
+}
@@ -1,26 +1,376 @@
-use std::collections::HashMap;
-use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use clap::ValueEnum;
+use serde::{Serialize, Deserialize};
+use crate::baseline::BaselineStatus;
+use crate::similarity::SimilarityAlgorithm;
 
-#[derive(Debug, Serialize, Clone)] 
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Route {
     pub path: String,
     pub method: String,
     pub file_path: String,
     pub line_number: usize,
+    pub column: usize,
+    /// Binding of the app or router instance the route is registered on, in the file that creates it
+    pub router: String,
+    /// Position of the route in the order Express registers (and therefore matches) handlers
+    pub order: usize,
+    /// The dispatch tree the route was collected under: an app, or a router that is never
+    /// mounted. Only routes in the same tree can shadow each other.
+    #[serde(default, skip_serializing)]
+    pub root: usize,
+    /// Mount points the route's router is reached through, outermost first
+    pub mount_chain: Vec<MountPoint>,
+    /// Middleware that runs before the route's handler, in the order Express runs it
+    #[serde(default)]
+    pub middleware: Vec<Middleware>,
+    /// Suppression comments covering the route's registration
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressions: Vec<Suppression>,
+    /// Source lines around the route's registration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<SourceSnippet>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+/// Lines of source around a route registration
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SourceSnippet {
+    /// Line number of the first line in `lines`
+    pub start_line: usize,
+    pub lines: Vec<String>,
+}
+
+/// A `use()` call that mounts a router under a path prefix
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MountPoint {
+    pub prefix: String,
+    pub file_path: String,
+    pub line_number: usize,
+}
+
+/// A middleware function registered with `use()` or passed to a route before its handler
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Middleware {
+    /// How the middleware is written, e.g. `auth` or `express.json()`
+    pub name: String,
+    /// Binding of the app or router it is registered on
+    pub router: String,
+    /// Path the `use()` call registers it for; `None` for a route's own arguments
+    pub prefix: Option<String>,
+    pub file_path: String,
+    pub line_number: usize,
+}
+
+/// An `insidious-ignore-next-line` or `insidious-disable` comment in the source
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Suppression {
+    pub kind: SuppressionKind,
+    /// Conflict types the comment names, e.g. `shadowed-route`; empty suppresses every type
+    pub rules: Vec<String>,
+    pub file_path: String,
+    pub line_number: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuppressionKind {
+    /// Covers the routes registered on the line after the comment
+    IgnoreNextLine,
+    /// Covers every route from the comment to the next `insidious-enable` or the end of the file
+    Disable,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RouteConflict {
     pub route1: Route,
     pub route2: Route,
     pub similarity: f64,
-    pub conflict_type: String,
+    pub conflict_type: ConflictType,
+    pub severity: Severity,
+    /// A concrete URL both routes match
+    pub sample_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictType {
+    #[serde(rename = "Exact Match")]
+    ExactMatch,
+    #[serde(rename = "Parameter Conflict")]
+    ParameterConflict,
+    /// `route2` can never be reached because the earlier `route1` matches every request it would
+    #[serde(rename = "Shadowed Route")]
+    ShadowedRoute,
+}
+
+/// How serious a finding is; ordered from least to most severe
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A conflict left out of the results by a suppression comment
+#[derive(Debug, Serialize, Clone)]
+pub struct SuppressedConflict {
+    pub conflict: RouteConflict,
+    pub suppression: Suppression,
+}
+
+/// The analyzed routes grouped by file, router and method, with summary counts
+#[derive(Debug, Serialize)]
+pub struct RouteInventory {
+    pub method_counts: BTreeMap<String, usize>,
+    /// Number of routes under each first path segment, e.g. `/users`
+    pub prefix_counts: BTreeMap<String, usize>,
+    pub files: Vec<FileRoutes>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct FileRoutes {
+    pub file_path: String,
+    pub route_count: usize,
+    pub routers: Vec<RouterRoutes>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouterRoutes {
+    pub router: String,
+    pub methods: Vec<MethodRoutes>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MethodRoutes {
+    pub method: String,
+    /// Indices into `AnalysisReport::routes`
+    pub routes: Vec<usize>,
+}
+
+/// The similarity score of two distinct route paths
+#[derive(Debug, Serialize)]
+pub struct PathSimilarity {
+    pub path1: String,
+    pub path2: String,
+    pub similarity: f64,
+}
+
+/// Version of the JSON report layout, raised whenever a change would break readers
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Serialize)]
 pub struct AnalysisReport {
+    pub schema_version: u32,
+    /// Directory the analysis ran on; file paths in the report start with it
+    pub project_directory: String,
+    /// Every route analyzed, in registration order
+    pub routes: Vec<Route>,
+    pub inventory: RouteInventory,
     pub conflicts: Vec<RouteConflict>,
     pub total_routes: usize,
     pub conflict_count: usize,
-    pub similarity_matrix: HashMap<String, f64>,
-}
\ No newline at end of file
+    /// Algorithm the similarity scores were computed with
+    pub similarity_algorithm: SimilarityAlgorithm,
+    /// Pairs of distinct paths scoring above the similarity threshold, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub similarity_matrix: Option<Vec<PathSimilarity>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressed_conflicts: Vec<SuppressedConflict>,
+    /// Suppression comments that did not suppress any conflict
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unused_suppressions: Vec<Suppression>,
+    /// Set when a baseline of accepted conflicts was applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineStatus>,
+}
+
+impl SourceSnippet {
+    /// Capture the lines within `context` lines of `line_number`, if the source has that line
+    pub fn capture(source: &str, line_number: usize, context: usize) -> Option<SourceSnippet> {
+        if line_number == 0 {
+            return None;
+        }
+
+        let start_line = line_number.saturating_sub(context).max(1);
+        let lines: Vec<String> = source.lines()
+            .skip(start_line - 1)
+            .take(line_number + context + 1 - start_line)
+            .map(str::to_string)
+            .collect();
+
+        (start_line + lines.len() > line_number).then_some(SourceSnippet { start_line, lines })
+    }
+
+    /// Each line with its line number
+    pub fn numbered_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.lines.iter().enumerate().map(|(i, line)| (self.start_line + i, line.as_str()))
+    }
+}
+
+impl Route {
+    /// A route registered directly on `app` in `project/app.js`, one line per `order`
+    #[cfg(test)]
+    pub fn for_tests(method: &str, path: &str, order: usize) -> Route {
+        Route {
+            path: path.to_string(),
+            method: method.to_string(),
+            file_path: "project/app.js".to_string(),
+            line_number: order + 1,
+            column: 1,
+            router: "app".to_string(),
+            order,
+            root: 0,
+            mount_chain: Vec::new(),
+            middleware: Vec::new(),
+            suppressions: Vec::new(),
+            snippet: None,
+        }
+    }
+
+    /// Human readable description of the mount chain, e.g. `/api -> /users`
+    pub fn mount_description(&self) -> Option<String> {
+        if self.mount_chain.is_empty() {
+            return None;
+        }
+
+        let prefixes: Vec<&str> = self.mount_chain.iter().map(|m| m.prefix.as_str()).collect();
+        Some(prefixes.join(" -> "))
+    }
+
+    /// Human readable description of the middleware chain, e.g. `cors() -> auth`
+    pub fn middleware_description(&self) -> Option<String> {
+        if self.middleware.is_empty() {
+            return None;
+        }
+
+        let names: Vec<&str> = self.middleware.iter().map(|m| m.name.as_str()).collect();
+        Some(names.join(" -> "))
+    }
+
+    /// First segment of the route's full path, e.g. `/users` for `/users/:id`
+    pub fn top_level_prefix(&self) -> String {
+        match self.path.trim_start_matches('/').split('/').next() {
+            Some(segment) if !segment.is_empty() => format!("/{}", segment),
+            _ => "/".to_string(),
+        }
+    }
+
+    /// Identifies a registration reached through one mount chain: file, line, column, method and full path
+    pub fn identity(&self) -> RouteIdentity<'_> {
+        (&self.file_path, self.line_number, self.column, &self.method, &self.path)
+    }
+}
+
+/// See `Route::identity`
+pub type RouteIdentity<'a> = (&'a str, usize, usize, &'a str, &'a str);
+
+impl AnalysisReport {
+    /// Indices of the conflicts each route is involved in, built once so formatters do not
+    /// rescan every conflict for every route
+    pub fn conflicts_by_route(&self) -> HashMap<RouteIdentity<'_>, Vec<usize>> {
+        let mut conflicts: HashMap<RouteIdentity, Vec<usize>> = HashMap::new();
+        for (index, conflict) in self.conflicts.iter().enumerate() {
+            conflicts.entry(conflict.route1.identity()).or_default().push(index);
+            if conflict.route2.identity() != conflict.route1.identity() {
+                conflicts.entry(conflict.route2.identity()).or_default().push(index);
+            }
+        }
+        conflicts
+    }
+}
+
+impl ConflictType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictType::ExactMatch => "Exact Match",
+            ConflictType::ParameterConflict => "Parameter Conflict",
+            ConflictType::ShadowedRoute => "Shadowed Route",
+        }
+    }
+
+    /// Name used for the conflict type in suppression comments
+    pub fn rule_name(&self) -> &'static str {
+        match self {
+            ConflictType::ExactMatch => "exact-match",
+            ConflictType::ParameterConflict => "parameter-conflict",
+            ConflictType::ShadowedRoute => "shadowed-route",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            ConflictType::ExactMatch | ConflictType::ShadowedRoute => Severity::Error,
+            ConflictType::ParameterConflict => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for ConflictType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// Process exit code used by `check` when this is the most severe failing finding
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Severity::Warning => 3,
+            Severity::Error => 4,
+        }
+    }
+}
+
+impl Suppression {
+    pub fn applies_to(&self, conflict_type: ConflictType) -> bool {
+        self.rules.is_empty() || self.rules.iter().any(|rule| rule == conflict_type.rule_name())
+    }
+
+    /// The comment as written, e.g. `insidious-disable shadowed-route`
+    pub fn directive(&self) -> String {
+        let name = match self.kind {
+            SuppressionKind::IgnoreNextLine => "insidious-ignore-next-line",
+            SuppressionKind::Disable => "insidious-disable",
+        };
+
+        if self.rules.is_empty() {
+            name.to_string()
+        } else {
+            format!("{} {}", name, self.rules.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl RouteConflict {
+    /// One sentence explaining the finding
+    pub fn describe(&self) -> String {
+        match self.conflict_type {
+            ConflictType::ExactMatch => format!(
+                "{} {} is registered more than once",
+                self.route1.method, self.route1.path
+            ),
+            ConflictType::ParameterConflict => format!(
+                "{} {} and {} {} can match the same requests{}",
+                self.route1.method, self.route1.path, self.route2.method, self.route2.path,
+                self.sample_url.as_ref().map(|url| format!(", e.g. {}", url)).unwrap_or_default()
+            ),
+            ConflictType::ShadowedRoute => format!(
+                "{} {} can never be reached: {} {} is registered earlier and captures every request it would handle",
+                self.route2.method, self.route2.path, self.route1.method, self.route1.path
+            ),
+        }
+    }
+}
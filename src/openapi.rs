@@ -0,0 +1,511 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use crate::cli::AnalysisConfig;
+use crate::models::Route;
+use crate::output::relative_file;
+use crate::route_pattern::{closing_paren, is_word_char, parameter_name_len, ExpressVersion};
+use crate::utils::join_route_paths;
+
+/// Methods an OpenAPI 3.1 path item has an operation for, in the order they are written
+const OPENAPI_METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// An OpenAPI 3.1 document describing the routes of a project
+#[derive(Serialize)]
+pub struct OpenApiDocument {
+    pub openapi: &'static str,
+    pub info: Info,
+    pub tags: Vec<Tag>,
+    pub paths: BTreeMap<String, PathItem>,
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    pub title: String,
+    pub version: String,
+}
+
+#[derive(Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub description: String,
+}
+
+/// The operations available on one path template
+#[derive(Default, Serialize)]
+pub struct PathItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub get: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub put: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Operation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<Operation>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Operation {
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Parameter>,
+    pub responses: BTreeMap<String, Response>,
+    /// The route path as written, when it differs from the path template
+    #[serde(rename = "x-express-path", skip_serializing_if = "Option::is_none")]
+    pub express_path: Option<String>,
+    /// Set to `all` for operations created from `app.all()`
+    #[serde(rename = "x-express-method", skip_serializing_if = "Option::is_none")]
+    pub express_method: Option<String>,
+    #[serde(rename = "x-source-location")]
+    pub source_location: SourceLocation,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Parameter {
+    pub name: String,
+    #[serde(rename = "in")]
+    pub location: &'static str,
+    pub required: bool,
+    pub schema: Schema,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Schema {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// The parameter's inline regex, from Express 4 paths such as `/:id(\\d+)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Response {
+    pub description: String,
+}
+
+/// Where the route behind an operation is registered
+#[derive(Clone, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A piece of a route path
+enum Piece {
+    Text(String),
+    Param { name: String, pattern: Option<String> },
+    /// Pieces that may be left out, from `:id?` in Express 4 and `{...}` in Express 5
+    Optional(Vec<Piece>),
+}
+
+impl OpenApiDocument {
+    /// Describe the routes as OpenAPI operations, along with the routes whose method
+    /// OpenAPI 3.1 cannot describe (e.g. `SEARCH`).
+    ///
+    /// When several routes share a path template and method, the one Express registers
+    /// first is described, since it is the one that handles requests.
+    pub fn new<'a>(routes: &'a [Route], config: &AnalysisConfig) -> (OpenApiDocument, Vec<&'a Route>) {
+        let mut routes: Vec<&Route> = routes.iter().collect();
+        routes.sort_by_key(|route| route.order);
+
+        let route_tags = route_tags(&routes, &config.project_directory);
+        let mut tags: Vec<Tag> = Vec::new();
+        let mut paths: BTreeMap<String, PathItem> = BTreeMap::new();
+        let mut unsupported = Vec::new();
+
+        for (route, (tag, description)) in routes.into_iter().zip(route_tags) {
+            let method = route.method.to_lowercase();
+            let methods: Vec<&str> = if method == "all" {
+                OPENAPI_METHODS.to_vec()
+            } else if let Some(method) = OPENAPI_METHODS.iter().find(|known| **known == method) {
+                vec![*method]
+            } else {
+                unsupported.push(route);
+                continue;
+            };
+
+            if !tags.iter().any(|existing| existing.name == tag) {
+                tags.push(Tag { name: tag.clone(), description });
+            }
+
+            for (template, parameters) in path_templates(&route.path, config.express_version) {
+                let operation = Operation {
+                    tags: vec![tag.clone()],
+                    parameters,
+                    responses: BTreeMap::from([
+                        ("default".to_string(), Response { description: "Default response".to_string() }),
+                    ]),
+                    express_path: (template != route.path).then(|| route.path.clone()),
+                    express_method: (method == "all").then(|| method.clone()),
+                    source_location: SourceLocation {
+                        file: relative_file(route, &config.project_directory),
+                        line: route.line_number,
+                        column: route.column,
+                    },
+                };
+
+                let item = paths.entry(template).or_default();
+                for method in &methods {
+                    let slot = item.operation_mut(method);
+                    if slot.is_none() {
+                        *slot = Some(operation.clone());
+                    }
+                }
+            }
+        }
+
+        let document = OpenApiDocument {
+            openapi: "3.1.0",
+            info: project_info(&config.project_directory),
+            tags,
+            paths,
+        };
+        (document, unsupported)
+    }
+
+    pub fn operation_count(&self) -> usize {
+        self.paths
+            .values()
+            .map(|item| OPENAPI_METHODS.iter().filter(|method| item.operation(method).is_some()).count())
+            .sum()
+    }
+}
+
+impl PathItem {
+    fn operation(&self, method: &str) -> &Option<Operation> {
+        match method {
+            "get" => &self.get,
+            "put" => &self.put,
+            "post" => &self.post,
+            "delete" => &self.delete,
+            "options" => &self.options,
+            "head" => &self.head,
+            "patch" => &self.patch,
+            _ => &self.trace,
+        }
+    }
+
+    fn operation_mut(&mut self, method: &str) -> &mut Option<Operation> {
+        match method {
+            "get" => &mut self.get,
+            "put" => &mut self.put,
+            "post" => &mut self.post,
+            "delete" => &mut self.delete,
+            "options" => &mut self.options,
+            "head" => &mut self.head,
+            "patch" => &mut self.patch,
+            _ => &mut self.trace,
+        }
+    }
+}
+
+/// Title and version from the project's package.json, falling back to the directory name
+fn project_info(project_directory: &str) -> Info {
+    let package: Option<Value> = fs::read_to_string(Path::new(project_directory).join("package.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    let field = |name: &str| {
+        package.as_ref()
+            .and_then(|package| package.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    let directory_name = || {
+        fs::canonicalize(project_directory)
+            .ok()
+            .and_then(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "API".to_string())
+    };
+
+    Info {
+        title: field("name").unwrap_or_else(directory_name),
+        version: field("version").unwrap_or_else(|| "0.0.0".to_string()),
+    }
+}
+
+/// The full prefix a route's router is mounted under, e.g. `/api/users`
+fn mount_prefix(route: &Route) -> Option<String> {
+    if route.mount_chain.is_empty() {
+        return None;
+    }
+    Some(route.mount_chain.iter().fold(String::new(), |prefix, mount| join_route_paths(&prefix, &mount.prefix)))
+}
+
+/// Name and description of each route's tag.
+///
+/// Routes of mounted routers are tagged with the mount prefix. Other routes are tagged with
+/// the binding of their app or router, plus its file when several files use the same binding.
+fn route_tags(routes: &[&Route], project_directory: &str) -> Vec<(String, String)> {
+    let mut files_per_router: HashMap<&str, HashSet<String>> = HashMap::new();
+    for route in routes.iter().filter(|route| route.mount_chain.is_empty()) {
+        files_per_router.entry(&route.router).or_default().insert(relative_file(route, project_directory));
+    }
+
+    routes
+        .iter()
+        .map(|route| {
+            let file = relative_file(route, project_directory);
+            match mount_prefix(route) {
+                Some(prefix) => (prefix.clone(), format!("Routes mounted at {}", prefix)),
+                None => {
+                    let name = if files_per_router[route.router.as_str()].len() > 1 {
+                        format!("{} ({})", route.router, file)
+                    } else {
+                        route.router.clone()
+                    };
+                    (name, format!("Routes registered on {} in {}", route.router, file))
+                }
+            }
+        })
+        .collect()
+}
+
+/// OpenAPI path templates for an Express route path with their path parameters.
+///
+/// A path with optional parts becomes one template with and one without each part, since
+/// OpenAPI path parameters are always required. Paths that cannot be parsed are used as written.
+pub fn path_templates(path: &str, version: ExpressVersion) -> Vec<(String, Vec<Parameter>)> {
+    let pieces = match version {
+        ExpressVersion::Express4 => Some(express4_pieces(path)),
+        ExpressVersion::Express5 => express5_pieces(path),
+    };
+    let Some(pieces) = pieces else {
+        return vec![(path.to_string(), Vec::new())];
+    };
+
+    expand(&pieces, vec![(String::new(), Vec::new())])
+        .into_iter()
+        .map(|(template, parameters)| {
+            let template = if template.is_empty() { "/".to_string() } else { template };
+            (template, parameters)
+        })
+        .collect()
+}
+
+/// Append each piece to every template, branching on optional pieces
+fn expand(pieces: &[Piece], mut templates: Vec<(String, Vec<Parameter>)>) -> Vec<(String, Vec<Parameter>)> {
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => {
+                for (template, _) in &mut templates {
+                    template.push_str(text);
+                }
+            },
+            Piece::Param { name, pattern } => {
+                for (template, parameters) in &mut templates {
+                    template.push_str(&format!("{{{}}}", name));
+                    parameters.push(Parameter {
+                        name: name.clone(),
+                        location: "path",
+                        required: true,
+                        schema: Schema {
+                            kind: "string",
+                            pattern: pattern.as_ref().map(|pattern| format!("^(?:{})$", pattern)),
+                        },
+                    });
+                }
+            },
+            Piece::Optional(inner) => {
+                let with = expand(inner, templates.clone());
+                templates.extend(with);
+            },
+        }
+    }
+    templates
+}
+
+/// Split an Express 4 path into pieces, following path-to-regexp 0.1 syntax
+fn express4_pieces(path: &str) -> Vec<Piece> {
+    let chars: Vec<char> = path.chars().collect();
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    // Express numbers unnamed wildcards from 0
+    let mut wildcards = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            ':' if chars.get(i + 1).is_some_and(|c| is_word_char(*c)) => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+
+                let mut pattern = None;
+                if chars.get(i) == Some(&'(') {
+                    if let Some(end) = closing_paren(&chars, i) {
+                        pattern = Some(chars[i + 1..end].iter().collect());
+                        i = end + 1;
+                    }
+                }
+                if chars.get(i) == Some(&'*') {
+                    i += 1;
+                }
+
+                let param = Piece::Param { name, pattern };
+                if chars.get(i) == Some(&'?') {
+                    // An optional parameter takes its leading slash with it
+                    let slash = text.ends_with('/');
+                    if slash {
+                        text.pop();
+                    }
+                    flush(&mut text, &mut pieces);
+                    let mut optional = Vec::new();
+                    if slash {
+                        optional.push(Piece::Text("/".to_string()));
+                    }
+                    optional.push(param);
+                    pieces.push(Piece::Optional(optional));
+                    i += 1;
+                } else {
+                    flush(&mut text, &mut pieces);
+                    pieces.push(param);
+                }
+                continue;
+            },
+            '*' => {
+                flush(&mut text, &mut pieces);
+                pieces.push(Piece::Param { name: wildcards.to_string(), pattern: None });
+                wildcards += 1;
+            },
+            c => text.push(c),
+        }
+        i += 1;
+    }
+
+    flush(&mut text, &mut pieces);
+    pieces
+}
+
+/// Split an Express 5 path into pieces, following path-to-regexp 8 syntax
+fn express5_pieces(path: &str) -> Option<Vec<Piece>> {
+    let chars: Vec<char> = path.chars().collect();
+    // The pieces of the path and of each open `{` group
+    let mut groups: Vec<Vec<Piece>> = vec![Vec::new()];
+    let mut text = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            ':' | '*' => {
+                i += 1;
+                let name_len = parameter_name_len(&chars[i..]);
+                if name_len == 0 {
+                    return None;
+                }
+                let name: String = chars[i..i + name_len].iter().collect();
+                i += name_len;
+
+                let pieces = groups.last_mut()?;
+                flush(&mut text, pieces);
+                pieces.push(Piece::Param { name: name.trim_matches('"').to_string(), pattern: None });
+                continue;
+            },
+            '{' => {
+                flush(&mut text, groups.last_mut()?);
+                groups.push(Vec::new());
+            },
+            '}' => {
+                if groups.len() == 1 {
+                    return None;
+                }
+                let mut group = groups.pop()?;
+                flush(&mut text, &mut group);
+                groups.last_mut()?.push(Piece::Optional(group));
+            },
+            '\\' => {
+                i += 1;
+                if let Some(c) = chars.get(i) {
+                    text.push(*c);
+                }
+            },
+            '(' | ')' | '[' | ']' | '?' | '+' | '!' => return None,
+            c => text.push(c),
+        }
+        i += 1;
+    }
+
+    if groups.len() > 1 {
+        return None;
+    }
+    let mut pieces = groups.pop()?;
+    flush(&mut text, &mut pieces);
+    Some(pieces)
+}
+
+/// Move collected literal text into the pieces
+fn flush(text: &mut String, pieces: &mut Vec<Piece>) {
+    if !text.is_empty() {
+        pieces.push(Piece::Text(std::mem::take(text)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates(path: &str, version: ExpressVersion) -> Vec<String> {
+        path_templates(path, version).into_iter().map(|(template, _)| template).collect()
+    }
+
+    fn parameter_names(path: &str, version: ExpressVersion) -> Vec<Vec<String>> {
+        path_templates(path, version)
+            .into_iter()
+            .map(|(_, parameters)| parameters.into_iter().map(|parameter| parameter.name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn each_optional_piece_doubles_the_templates() {
+        assert_eq!(templates("/users/:id?", ExpressVersion::Express4), ["/users", "/users/{id}"]);
+        assert_eq!(
+            templates("/archive/:year?/:month?", ExpressVersion::Express4),
+            ["/archive", "/archive/{year}", "/archive/{month}", "/archive/{year}/{month}"]
+        );
+        assert_eq!(
+            templates("/files{/:dir}{.:ext}", ExpressVersion::Express5),
+            ["/files", "/files/{dir}", "/files.{ext}", "/files/{dir}.{ext}"]
+        );
+        assert_eq!(templates("/:a?/:b?/:c?", ExpressVersion::Express4).len(), 8);
+        assert_eq!(templates("/:id?", ExpressVersion::Express4), ["/", "/{id}"]);
+    }
+
+    #[test]
+    fn parameters_and_wildcards_become_named_placeholders() {
+        assert_eq!(templates("/users/:userId/posts/:postId", ExpressVersion::Express4), ["/users/{userId}/posts/{postId}"]);
+        assert_eq!(templates("/assets/*/thumb/*", ExpressVersion::Express4), ["/assets/{0}/thumb/{1}"]);
+        assert_eq!(templates("/assets/*path", ExpressVersion::Express5), ["/assets/{path}"]);
+        assert_eq!(templates("/:\"user id\"", ExpressVersion::Express5), ["/{user id}"]);
+
+        assert_eq!(parameter_names("/archive/:year?/:month", ExpressVersion::Express4), [vec!["month"], vec!["year", "month"]]);
+    }
+
+    #[test]
+    fn inline_regexes_become_anchored_schema_patterns() {
+        let templates = path_templates("/users/:id(\\d+)", ExpressVersion::Express4);
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].0, "/users/{id}");
+        assert_eq!(templates[0].1[0].schema.pattern.as_deref(), Some("^(?:\\d+)$"));
+        assert!(templates[0].1[0].required);
+    }
+
+    #[test]
+    fn paths_that_cannot_be_parsed_are_used_as_written() {
+        assert_eq!(templates("/users/(unclosed", ExpressVersion::Express5), ["/users/(unclosed"]);
+        assert_eq!(templates("/users{/:id", ExpressVersion::Express5), ["/users{/:id"]);
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::path::Path;
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::models::{Route, RouteConflict};
+
+/// Output format of `insidious diff`, `insidious compare` and `insidious spec-check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// Suited to pull request comments
+    Markdown,
+}
+
+/// One kind of change, listed one item per line
+pub struct Section {
+    pub title: &'static str,
+    /// Put in front of each line in text output, e.g. `+` for additions
+    pub marker: char,
+    pub lines: Vec<String>,
+}
+
+/// A comparison rendered as a heading, a Markdown summary table and a list of sections
+pub trait SectionedOutput: Serialize {
+    /// What is being compared, e.g. `Route changes from main to HEAD`, with names in
+    /// backticks when `markdown` is set
+    fn heading(&self, markdown: bool) -> String;
+
+    /// Counts of each kind of change as a Markdown table
+    fn summary_table(&self) -> String;
+
+    fn sections(&self) -> Vec<Section>;
+
+    fn render(&self, format: OutputFormat) -> Result<String, String> {
+        match format {
+            OutputFormat::Text => Ok(render_text(self)),
+            OutputFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|err| format!("Error: Could not serialize the output: {}", err)),
+            OutputFormat::Markdown => Ok(render_markdown(self)),
+        }
+    }
+}
+
+fn render_text<T: SectionedOutput + ?Sized>(output: &T) -> String {
+    let mut text = format!("{}\n", output.heading(false));
+
+    for section in output.sections() {
+        text.push_str(&format!("\n{}: {}\n", section.title, section.lines.len()));
+        for line in section.lines {
+            text.push_str(&format!("  {} {}\n", section.marker, line));
+        }
+    }
+
+    text
+}
+
+fn render_markdown<T: SectionedOutput + ?Sized>(output: &T) -> String {
+    let mut md = format!("## {}\n\n", output.heading(true));
+    md.push_str(&output.summary_table());
+
+    for section in output.sections() {
+        if section.lines.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("\n### {}\n\n", section.title));
+        for line in section.lines {
+            md.push_str(&format!("- {}\n", line));
+        }
+    }
+
+    md
+}
+
+/// Items of two lists paired up by key, each item used at most once, in list order
+pub struct KeyMatching {
+    /// Indices of matched items in the previous and current lists
+    pub pairs: Vec<(usize, usize)>,
+    /// Indices of previous items without a match
+    pub removed: Vec<usize>,
+    /// Indices of current items without a match
+    pub added: Vec<usize>,
+}
+
+impl KeyMatching {
+    pub fn new<K: Eq + Hash>(previous: &[K], current: &[K]) -> KeyMatching {
+        let mut unmatched: HashMap<&K, VecDeque<usize>> = HashMap::new();
+        for (index, key) in previous.iter().enumerate() {
+            unmatched.entry(key).or_default().push_back(index);
+        }
+
+        let mut pairs = Vec::new();
+        let mut added = Vec::new();
+        for (index, key) in current.iter().enumerate() {
+            match unmatched.get_mut(key).and_then(|indices| indices.pop_front()) {
+                Some(previous_index) => pairs.push((previous_index, index)),
+                None => added.push(index),
+            }
+        }
+
+        let mut removed: Vec<usize> = unmatched.into_values().flatten().collect();
+        removed.sort_unstable();
+
+        KeyMatching { pairs, removed, added }
+    }
+}
+
+/// Routes are identified by method and full path
+pub fn route_key(route: &Route) -> (String, String) {
+    (route.method.clone(), route.path.clone())
+}
+
+/// A route on one line: method, path and location
+pub fn route_line(route: &Route) -> String {
+    format!("{} {} ({}:{})", route.method, route.path, route.file_path, route.line_number)
+}
+
+/// A conflict on one line: severity, type, description and both locations
+pub fn conflict_line(conflict: &RouteConflict) -> String {
+    format!(
+        "{} {}: {} ({}:{}, {}:{})",
+        conflict.severity,
+        conflict.conflict_type,
+        conflict.describe(),
+        conflict.route1.file_path,
+        conflict.route1.line_number,
+        conflict.route2.file_path,
+        conflict.route2.line_number
+    )
+}
+
+/// A route's file relative to the project directory, with `/` separators on every platform
+pub fn relative_file(route: &Route, project_directory: &str) -> String {
+    let path = Path::new(&route.file_path);
+    let relative = path.strip_prefix(project_directory).unwrap_or(path);
+
+    relative.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -1,140 +1,394 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
-use std::path::Path;
-use crate::models::{Route, RouteConflict, AnalysisReport};
-use crate::utils::calculate_path_similarity;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+use rayon::prelude::*;
+use serde_json::{json, Value};
+use crate::cli::AnalysisConfig;
+use crate::conflict_analyzer::ConflictAnalysis;
+use crate::html_report;
+use crate::models::{
+    ConflictType, Route, RouteConflict, AnalysisReport, RouteInventory, FileRoutes, RouterRoutes,
+    MethodRoutes, PathSimilarity, REPORT_SCHEMA_VERSION,
+};
+use crate::similarity::{path_similarity, SimilarityAlgorithm};
+
+pub fn create_analysis_report(
+    routes: Vec<Route>,
+    analysis: ConflictAnalysis,
+    config: &AnalysisConfig,
+) -> AnalysisReport {
+    let similarity_matrix = config.similarity_matrix
+        .then(|| similarity_matrix(&routes, config.similarity_algorithm, config.similarity_threshold));
+
+    let mut routes = routes;
+    routes.sort_by_key(|route| route.order);
 
-pub fn create_analysis_report(routes: Vec<Route>, conflicts: Vec<RouteConflict>) -> AnalysisReport {
-    let mut similarity_matrix = HashMap::new();
-    
-    for route1 in routes.iter() {
-        for route2 in routes.iter() {
-            if route1.path != route2.path {
-                let key = format!("{} <-> {}", route1.path, route2.path);
-                let similarity = calculate_path_similarity(&route1.path, &route2.path);
-                similarity_matrix.insert(key, similarity);
-            }
-        }
-    }
-    
     AnalysisReport {
-        conflicts: conflicts.clone(),
+        schema_version: REPORT_SCHEMA_VERSION,
+        project_directory: config.project_directory.clone(),
+        inventory: build_inventory(&routes),
+        conflict_count: analysis.conflicts.len(),
+        conflicts: analysis.conflicts,
         total_routes: routes.len(),
-        conflict_count: conflicts.len(),
+        similarity_algorithm: config.similarity_algorithm,
         similarity_matrix,
+        suppressed_conflicts: analysis.suppressed,
+        unused_suppressions: analysis.unused_suppressions,
+        baseline: None,
+        routes,
+    }
+}
+
+/// Score each unordered pair of distinct paths once, keeping those above `threshold`
+fn similarity_matrix(routes: &[Route], algorithm: SimilarityAlgorithm, threshold: f64) -> Vec<PathSimilarity> {
+    let paths: Vec<&str> = routes.iter()
+        .map(|route| route.path.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    (0..paths.len())
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let paths = &paths;
+            (i + 1..paths.len()).filter_map(move |j| {
+                let similarity = path_similarity(paths[i], paths[j], algorithm);
+                (similarity > threshold).then(|| PathSimilarity {
+                    path1: paths[i].to_string(),
+                    path2: paths[j].to_string(),
+                    similarity,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Group routes by file, router and method and count them per method and top-level prefix
+fn build_inventory(routes: &[Route]) -> RouteInventory {
+    let mut method_counts = BTreeMap::new();
+    let mut prefix_counts = BTreeMap::new();
+    let mut grouped: BTreeMap<&str, BTreeMap<&str, BTreeMap<&str, Vec<usize>>>> = BTreeMap::new();
+
+    for (index, route) in routes.iter().enumerate() {
+        *method_counts.entry(route.method.clone()).or_insert(0) += 1;
+        *prefix_counts.entry(route.top_level_prefix()).or_insert(0) += 1;
+        grouped
+            .entry(route.file_path.as_str())
+            .or_default()
+            .entry(route.router.as_str())
+            .or_default()
+            .entry(route.method.as_str())
+            .or_default()
+            .push(index);
     }
+
+    let files = grouped
+        .into_iter()
+        .map(|(file_path, routers)| {
+            let routers: Vec<RouterRoutes> = routers
+                .into_iter()
+                .map(|(router, methods)| RouterRoutes {
+                    router: router.to_string(),
+                    methods: methods
+                        .into_iter()
+                        .map(|(method, routes)| MethodRoutes { method: method.to_string(), routes })
+                        .collect(),
+                })
+                .collect();
+            let route_count = routers.iter()
+                .flat_map(|router| &router.methods)
+                .map(|method| method.routes.len())
+                .sum();
+
+            FileRoutes { file_path: file_path.to_string(), route_count, routers }
+        })
+        .collect();
+
+    RouteInventory { method_counts, prefix_counts, files }
+}
+
+/// Print a route's source snippet with line numbers, marking (and on a terminal, highlighting) its line
+fn print_snippet(route: &Route, color: bool) {
+    let Some(snippet) = &route.snippet else {
+        return;
+    };
+
+    let width = (snippet.start_line + snippet.lines.len()).to_string().len();
+    for (number, line) in snippet.numbered_lines() {
+        if number != route.line_number {
+            println!("    {:>width$} | {}", number, line, width = width);
+        } else if color {
+            println!("  \x1b[1;33m> {:>width$} | {}\x1b[0m", number, line, width = width);
+        } else {
+            println!("  > {:>width$} | {}", number, line, width = width);
+        }
+    }
+}
+
+/// A fenced code block of a route's snippet, with its line marked by `>`
+fn markdown_snippet(route: &Route) -> String {
+    let Some(snippet) = &route.snippet else {
+        return String::new();
+    };
+
+    let language = Path::new(&route.file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("");
+    let width = (snippet.start_line + snippet.lines.len()).to_string().len();
+
+    let mut block = format!("\n```{}\n", language);
+    for (number, line) in snippet.numbered_lines() {
+        let marker = if number == route.line_number { ">" } else { " " };
+        block.push_str(&format!("{} {:>width$} | {}\n", marker, number, line, width = width));
+    }
+    block.push_str("```\n");
+
+    block
+}
+
+/// Counts formatted as `GET 3, POST 1`
+fn format_counts(counts: &BTreeMap<String, usize>) -> String {
+    counts.iter()
+        .map(|(name, count)| format!("{} {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub fn print_report_summary(report: &AnalysisReport) {
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+
     println!("\nRoute Analysis Report");
     println!("====================");
     println!("Total Routes: {}", report.total_routes);
+    if !report.routes.is_empty() {
+        println!("Routes by Method: {}", format_counts(&report.inventory.method_counts));
+        println!("Routes by Prefix: {}", format_counts(&report.inventory.prefix_counts));
+    }
     println!("Conflicts Found: {}", report.conflict_count);
+    if !report.suppressed_conflicts.is_empty() {
+        println!("Suppressed Conflicts: {}", report.suppressed_conflicts.len());
+    }
+    if let Some(baseline) = &report.baseline {
+        println!("Baselined Conflicts: {} (from {})", baseline.suppressed, baseline.path);
+    }
     
     if !report.conflicts.is_empty() {
         println!("\nConflicts:");
         for conflict in &report.conflicts {
-            println!("\nConflict Type: {}", conflict.conflict_type);
+            println!("\nConflict Type: {} ({})", conflict.conflict_type, conflict.severity);
+            println!("{}", conflict.describe());
             println!("Similarity: {:.2}%", conflict.similarity);
-            println!("Route 1: {} {} ({}:{})",
+            if let Some(url) = &conflict.sample_url {
+                println!("Sample URL: {}", url);
+            }
+            println!("Route 1: {} {} ({}:{}:{})",
                 conflict.route1.method,
                 conflict.route1.path,
                 conflict.route1.file_path,
-                conflict.route1.line_number
+                conflict.route1.line_number,
+                conflict.route1.column
             );
-            println!("Route 2: {} {} ({}:{})",
+            print_snippet(&conflict.route1, color);
+            println!("Route 2: {} {} ({}:{}:{})",
                 conflict.route2.method,
                 conflict.route2.path,
                 conflict.route2.file_path,
-                conflict.route2.line_number
+                conflict.route2.line_number,
+                conflict.route2.column
+            );
+            print_snippet(&conflict.route2, color);
+            for route in [&conflict.route1, &conflict.route2] {
+                if let Some(mounts) = route.mount_description() {
+                    println!("  {} {} mounted via {}", route.method, route.path, mounts);
+                }
+                if let Some(middleware) = route.middleware_description() {
+                    println!("  {} {} runs middleware {}", route.method, route.path, middleware);
+                }
+            }
+        }
+    }
+
+    if !report.suppressed_conflicts.is_empty() {
+        println!("\nSuppressed Conflicts:");
+        for entry in &report.suppressed_conflicts {
+            println!("  {} ({}) by {} at {}:{}",
+                entry.conflict.describe(),
+                entry.conflict.conflict_type,
+                entry.suppression.directive(),
+                entry.suppression.file_path,
+                entry.suppression.line_number
+            );
+        }
+    }
+
+    if !report.unused_suppressions.is_empty() {
+        println!("\nUnused Suppressions (no conflict matches them; remove them):");
+        for suppression in &report.unused_suppressions {
+            println!("  {}:{}: {}", suppression.file_path, suppression.line_number, suppression.directive());
+        }
+    }
+
+    if let Some(baseline) = report.baseline.as_ref().filter(|baseline| !baseline.stale.is_empty()) {
+        println!("\nStale Baseline Entries (no longer found; recreate the baseline to drop them):");
+        for entry in &baseline.stale {
+            println!("  {} [{}]: {} ({}) / {} ({})",
+                entry.conflict_type, entry.fingerprint,
+                entry.route1, entry.file1,
+                entry.route2, entry.file2
             );
         }
     }
 }
 
-pub fn save_json_report(report: &AnalysisReport, output_path: Option<&str>) {
-    let path = match output_path {
-        Some(path_str) => Path::new(path_str),
-        None => Path::new("route_analysis_report.json"),
-    };
-    
-    let json = serde_json::to_string_pretty(report).unwrap();
-    
-    fs::write(path, json).unwrap();
-    println!("\nDetailed JSON report saved to {}", path.display());
+/// Formats a report can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Html,
+    Markdown,
+    /// SARIF 2.1.0, for code scanning tools
+    Sarif,
+    /// JUnit XML with one test case per route
+    Junit,
+    /// Checkstyle XML with an error entry per conflicting route
+    Checkstyle,
 }
 
-pub fn save_html_report(report: &AnalysisReport, output_path: Option<&str>) {
-    let path = match output_path {
-        Some(path_str) => Path::new(path_str),
-        None => Path::new("route_analysis_report.html"),
-    };
-    
-    // HTML report template
-    let mut html = String::from(r#"<!DOCTYPE html>
-<html>
-<head>
-    <title>Express.js Route Analysis Report</title>
-    <style>
-        body { font-family: Arial, sans-serif; margin: 20px; }
-        table { border-collapse: collapse; width: 100%; }
-        th, td { border: 1px solid #ddd; padding: 8px; text-align: left; }
-        th { background-color: #f2f2f2; }
-        tr:nth-child(even) { background-color: #f9f9f9; }
-        .conflict { background-color: #ffecec; }
-    </style>
-</head>
-<body>
-    <h1>Express.js Route Analysis Report</h1>
-    <p>Total Routes: "#);
-    
-    html.push_str(&report.total_routes.to_string());
-    html.push_str("</p>\n<p>Conflicts Found: ");
-    html.push_str(&report.conflict_count.to_string());
-    html.push_str("</p>\n");
-    
-    if !report.conflicts.is_empty() {
-        html.push_str(r#"<h2>Conflicts</h2>
-    <table>
-        <tr>
-            <th>Conflict Type</th>
-            <th>Similarity</th>
-            <th>Route 1</th>
-            <th>Location 1</th>
-            <th>Route 2</th>
-            <th>Location 2</th>
-        </tr>
-"#);
-        
-        for conflict in &report.conflicts {
-            html.push_str("<tr class=\"conflict\">\n");
-            html.push_str(&format!("<td>{}</td>\n", conflict.conflict_type));
-            html.push_str(&format!("<td>{:.2}%</td>\n", conflict.similarity));
-            html.push_str(&format!("<td>{} {}</td>\n", conflict.route1.method, conflict.route1.path));
-            html.push_str(&format!("<td>{}:{}</td>\n", conflict.route1.file_path, conflict.route1.line_number));
-            html.push_str(&format!("<td>{} {}</td>\n", conflict.route2.method, conflict.route2.path));
-            html.push_str(&format!("<td>{}:{}</td>\n", conflict.route2.file_path, conflict.route2.line_number));
-            html.push_str("</tr>\n");
+/// Renders an analysis report in one output format
+pub trait ReportFormatter {
+    /// Name of the format in status messages, e.g. `HTML`
+    fn display_name(&self) -> &'static str;
+
+    /// File the report is written to when no output path is given
+    fn default_file_name(&self) -> &'static str;
+
+    fn render(&self, report: &AnalysisReport) -> Result<String, String>;
+}
+
+/// Renders a report into the contents of a report file
+type RenderFn = fn(&AnalysisReport) -> Result<String, String>;
+
+/// A formatter backed by one of the render functions in this module
+struct BuiltinFormatter {
+    display_name: &'static str,
+    default_file_name: &'static str,
+    render: RenderFn,
+}
+
+/// The formatter used for each report format
+pub struct FormatterRegistry {
+    formatters: HashMap<ReportFormat, Box<dyn ReportFormatter>>,
+}
+
+impl ReportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Sarif => "sarif",
+            ReportFormat::Junit => "junit",
+            ReportFormat::Checkstyle => "checkstyle",
         }
-        
-        html.push_str("</table>\n");
     }
-    
-    html.push_str("</body>\n</html>");
-    
-    fs::write(path, html).unwrap();
-    println!("\nDetailed HTML report saved to {}", path.display());
 }
 
-pub fn save_markdown_report(report: &AnalysisReport, output_path: Option<&str>) {
-    let path = match output_path {
-        Some(path_str) => Path::new(path_str),
-        None => Path::new("route_analysis_report.md"),
-    };
-    
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ReportFormatter for BuiltinFormatter {
+    fn display_name(&self) -> &'static str {
+        self.display_name
+    }
+
+    fn default_file_name(&self) -> &'static str {
+        self.default_file_name
+    }
+
+    fn render(&self, report: &AnalysisReport) -> Result<String, String> {
+        (self.render)(report)
+    }
+}
+
+impl FormatterRegistry {
+    /// Use `formatter` for `format`, replacing any formatter registered before
+    pub fn register(&mut self, format: ReportFormat, formatter: Box<dyn ReportFormatter>) {
+        self.formatters.insert(format, formatter);
+    }
+
+    pub fn get(&self, format: ReportFormat) -> Result<&dyn ReportFormatter, String> {
+        self.formatters
+            .get(&format)
+            .map(|formatter| formatter.as_ref())
+            .ok_or_else(|| format!("Error: No formatter is registered for the '{}' format", format))
+    }
+}
+
+impl Default for FormatterRegistry {
+    /// A registry with the formatter for every built-in format
+    fn default() -> Self {
+        let builtins: [(ReportFormat, &str, &str, RenderFn); 6] = [
+            (ReportFormat::Json, "JSON", "route_analysis_report.json", render_json),
+            (ReportFormat::Html, "HTML", "route_analysis_report.html", html_report::render_html),
+            (ReportFormat::Markdown, "Markdown", "route_analysis_report.md", render_markdown),
+            (ReportFormat::Sarif, "SARIF", "route_analysis_report.sarif", render_sarif),
+            (ReportFormat::Junit, "JUnit", "route_analysis_report.junit.xml", render_junit),
+            (ReportFormat::Checkstyle, "Checkstyle", "route_analysis_report.checkstyle.xml", render_checkstyle),
+        ];
+
+        let mut registry = FormatterRegistry { formatters: HashMap::new() };
+        for (format, display_name, default_file_name, render) in builtins {
+            registry.register(format, Box::new(BuiltinFormatter { display_name, default_file_name, render }));
+        }
+        registry
+    }
+}
+
+/// Render a report and write it to `output`: a file path, `-` for standard output, or the
+/// formatter's default file when `None`. Returns the file written, if any.
+pub fn write_report(
+    report: &AnalysisReport,
+    formatter: &dyn ReportFormatter,
+    output: Option<&str>,
+) -> Result<Option<PathBuf>, String> {
+    let contents = formatter.render(report)
+        .map_err(|err| format!("Error: Could not render {} report: {}", formatter.display_name(), err))?;
+
+    if output == Some("-") {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(contents.as_bytes())
+            .and_then(|_| if contents.ends_with('\n') { Ok(()) } else { stdout.write_all(b"\n") })
+            .map_err(|err| format!("Error: Could not write {} report to standard output: {}", formatter.display_name(), err))?;
+        return Ok(None);
+    }
+
+    let path = PathBuf::from(output.unwrap_or(formatter.default_file_name()));
+    fs::write(&path, contents)
+        .map_err(|err| format!("Error: Could not write {} report to '{}': {}", formatter.display_name(), path.display(), err))?;
+
+    Ok(Some(path))
+}
+
+fn render_json(report: &AnalysisReport) -> Result<String, String> {
+    serde_json::to_string_pretty(report).map_err(|err| err.to_string())
+}
+
+fn render_markdown(report: &AnalysisReport) -> Result<String, String> {
     let mut markdown = String::from("# Express.js Route Analysis Report\n\n");
     markdown.push_str(&format!("- **Total Routes:** {}\n", report.total_routes));
-    markdown.push_str(&format!("- **Conflicts Found:** {}\n\n", report.conflict_count));
+    markdown.push_str(&format!("- **Conflicts Found:** {}\n", report.conflict_count));
+    if let Some(baseline) = &report.baseline {
+        markdown.push_str(&format!("- **Baselined Conflicts:** {} (from {})\n", baseline.suppressed, baseline.path));
+    }
+    markdown.push('\n');
     
     if !report.conflicts.is_empty() {
         markdown.push_str("## Conflicts\n\n");
@@ -143,17 +397,402 @@ pub fn save_markdown_report(report: &AnalysisReport, output_path: Option<&str>)
         
         for conflict in &report.conflicts {
             markdown.push_str(&format!(
-                "| {} | {:.2}% | {} {} | {}:{} | {} {} | {}:{} |\n",
+                "| {} | {:.2}% | {} {} | {}:{}:{} | {} {} | {}:{}:{} |\n",
                 conflict.conflict_type,
                 conflict.similarity,
-                conflict.route1.method, conflict.route1.path,
+                conflict.route1.method, markdown_cell(&conflict.route1.path),
+                markdown_cell(&conflict.route1.file_path), conflict.route1.line_number, conflict.route1.column,
+                conflict.route2.method, markdown_cell(&conflict.route2.path),
+                markdown_cell(&conflict.route2.file_path), conflict.route2.line_number, conflict.route2.column
+            ));
+        }
+
+        markdown.push_str("\n## Conflict Details\n");
+        for (index, conflict) in report.conflicts.iter().enumerate() {
+            markdown.push_str(&format!(
+                "\n### {}. {} ({})\n\n{}\n",
+                index + 1, conflict.conflict_type, conflict.severity, conflict.describe()
+            ));
+            for route in [&conflict.route1, &conflict.route2] {
+                markdown.push_str(&format!(
+                    "\n`{} {}` at {}:{}:{}",
+                    route.method, route.path, route.file_path, route.line_number, route.column
+                ));
+                if let Some(mounts) = route.mount_description() {
+                    markdown.push_str(&format!(", mounted via {}", markdown_cell(&mounts)));
+                }
+                if let Some(middleware) = route.middleware_description() {
+                    markdown.push_str(&format!(", runs middleware {}", markdown_cell(&middleware)));
+                }
+                markdown.push('\n');
+                markdown.push_str(&markdown_snippet(route));
+            }
+        }
+    }
+
+    if !report.suppressed_conflicts.is_empty() {
+        markdown.push_str("\n## Suppressed Conflicts\n\n");
+        for entry in &report.suppressed_conflicts {
+            markdown.push_str(&format!(
+                "- {}: {} by `{}` at {}:{}\n",
+                entry.conflict.conflict_type, entry.conflict.describe(), entry.suppression.directive(),
+                entry.suppression.file_path, entry.suppression.line_number
+            ));
+        }
+    }
+
+    if !report.unused_suppressions.is_empty() {
+        markdown.push_str("\n## Unused Suppressions\n\n");
+        for suppression in &report.unused_suppressions {
+            markdown.push_str(&format!(
+                "- `{}` at {}:{}\n",
+                suppression.directive(), suppression.file_path, suppression.line_number
+            ));
+        }
+    }
+
+    if let Some(baseline) = report.baseline.as_ref().filter(|baseline| !baseline.stale.is_empty()) {
+        markdown.push_str("\n## Stale Baseline Entries\n\n");
+        for entry in &baseline.stale {
+            markdown.push_str(&format!(
+                "- {}: `{}` ({}) / `{}` ({})\n",
+                entry.conflict_type, entry.route1, entry.file1, entry.route2, entry.file2
+            ));
+        }
+    }
+    
+    if !report.routes.is_empty() {
+        markdown.push_str("\n## Routes\n\n");
+        markdown.push_str(&format!("- **By Method:** {}\n", format_counts(&report.inventory.method_counts)));
+        markdown.push_str(&format!("- **By Prefix:** {}\n", format_counts(&report.inventory.prefix_counts)));
+
+        for file in &report.inventory.files {
+            markdown.push_str(&format!("\n### {} ({} routes)\n\n", file.file_path, file.route_count));
+            markdown.push_str("| Router | Method | Path | Location | Mounted Via | Middleware |\n");
+            markdown.push_str("|--------|--------|------|----------|-------------|------------|\n");
+            for router in &file.routers {
+                for method in &router.methods {
+                    for &index in &method.routes {
+                        let route = &report.routes[index];
+                        markdown.push_str(&format!(
+                            "| {} | {} | `{}` | {}:{} | {} | {} |\n",
+                            markdown_cell(&router.router),
+                            method.method,
+                            markdown_cell(&route.path),
+                            route.line_number,
+                            route.column,
+                            markdown_cell(&route.mount_description().unwrap_or_default()),
+                            markdown_cell(&route.middleware_description().unwrap_or_default())
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(markdown)
+}
+
+/// Escape text for a Markdown table cell, where `|` would end the cell and a line break the row
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Conflict types in the order they are listed as SARIF rules
+const SARIF_RULES: [ConflictType; 3] = [
+    ConflictType::ExactMatch,
+    ConflictType::ParameterConflict,
+    ConflictType::ShadowedRoute,
+];
+
+fn render_sarif(report: &AnalysisReport) -> Result<String, String> {
+    let rules: Vec<Value> = SARIF_RULES.iter().map(|conflict_type| json!({
+        "id": conflict_type.rule_name(),
+        "name": conflict_type.as_str().replace(' ', ""),
+        "shortDescription": { "text": conflict_type.as_str() },
+        "fullDescription": { "text": rule_description(*conflict_type) },
+        "defaultConfiguration": { "level": conflict_type.severity().as_str() },
+    })).collect();
+
+    let mut results: Vec<Value> = report.conflicts
+        .iter()
+        .map(sarif_result)
+        .collect();
+    for entry in &report.suppressed_conflicts {
+        let mut result = sarif_result(&entry.conflict);
+        result["suppressions"] = json!([{
+            "kind": "inSource",
+            "justification": format!(
+                "{} at {}:{}",
+                entry.suppression.directive(), entry.suppression.file_path, entry.suppression.line_number
+            ),
+        }]);
+        results.push(result);
+    }
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "insidious",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+            "properties": {
+                "totalRoutes": report.total_routes,
+                "routesByMethod": report.inventory.method_counts,
+                "routesByPrefix": report.inventory.prefix_counts,
+            },
+        }]
+    });
+
+    serde_json::to_string_pretty(&sarif).map_err(|err| err.to_string())
+}
+
+fn rule_description(conflict_type: ConflictType) -> &'static str {
+    match conflict_type {
+        ConflictType::ExactMatch => "The same method and path are registered more than once; only the first handler is reached.",
+        ConflictType::ParameterConflict => "Two similar routes can match the same requests, so which handler runs depends on registration order.",
+        ConflictType::ShadowedRoute => "A route can never be reached because an earlier route matches every request it would handle.",
+    }
+}
+
+/// A SARIF result located at the first route, with the second route as a related location
+fn sarif_result(conflict: &RouteConflict) -> Value {
+    let rule_index = SARIF_RULES.iter().position(|rule| *rule == conflict.conflict_type);
+
+    json!({
+        "ruleId": conflict.conflict_type.rule_name(),
+        "ruleIndex": rule_index,
+        "level": conflict.severity.as_str(),
+        "message": { "text": conflict.describe() },
+        "locations": [{
+            "physicalLocation": sarif_physical_location(&conflict.route1),
+        }],
+        "relatedLocations": [{
+            "id": 1,
+            "physicalLocation": sarif_physical_location(&conflict.route2),
+            "message": { "text": format!("{} {}", conflict.route2.method, conflict.route2.path) },
+        }],
+        "properties": {
+            "similarity": conflict.similarity,
+            "sampleUrl": conflict.sample_url,
+        },
+    })
+}
+
+fn sarif_physical_location(route: &Route) -> Value {
+    json!({
+        "artifactLocation": { "uri": sarif_uri(&route.file_path) },
+        "region": {
+            "startLine": route.line_number,
+            "startColumn": route.column,
+        },
+    })
+}
+
+/// A file path as a SARIF URI: forward slashes, relative paths without a leading `./`
+fn sarif_uri(file_path: &str) -> String {
+    let uri = file_path.replace('\\', "/");
+    if Path::new(file_path).is_absolute() {
+        format!("file://{}", if uri.starts_with('/') { uri } else { format!("/{}", uri) })
+    } else {
+        uri.trim_start_matches("./").to_string()
+    }
+}
+
+fn render_junit(report: &AnalysisReport) -> Result<String, String> {
+    let conflicts_by_route = report.conflicts_by_route();
+    let failing = |route: &Route| -> Vec<&RouteConflict> {
+        conflicts_by_route
+            .get(&route.identity())
+            .map(|indices| indices.iter().map(|&index| &report.conflicts[index]).collect())
+            .unwrap_or_default()
+    };
+    let total_failures = report.routes.iter().filter(|route| !failing(route).is_empty()).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"insidious\" tests=\"{}\" failures=\"{}\">\n",
+        report.routes.len(), total_failures
+    ));
+
+    // One test suite per file and one test case per route, classed by the router it is registered on
+    for file in &report.inventory.files {
+        let routes: Vec<(&str, &Route)> = file.routers
+            .iter()
+            .flat_map(|router| {
+                router.methods
+                    .iter()
+                    .flat_map(|method| &method.routes)
+                    .map(move |&index| (router.router.as_str(), &report.routes[index]))
+            })
+            .collect();
+
+        let failures = routes.iter().filter(|(_, route)| !failing(route).is_empty()).count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            xml_escape(&file.file_path), routes.len(), failures
+        ));
+
+        for (router, route) in routes {
+            let name = format!("{} {}", route.method, route.path);
+            let attributes = format!(
+                "name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\"",
+                xml_escape(&name), xml_escape(router), xml_escape(&file.file_path), route.line_number
+            );
+
+            let conflicts = failing(route);
+            if conflicts.is_empty() {
+                xml.push_str(&format!("    <testcase {}/>\n", attributes));
+                continue;
+            }
+
+            let details: Vec<String> = conflicts.iter().map(|conflict| format!(
+                "{} ({}): {} [{}:{} / {}:{}]",
+                conflict.conflict_type, conflict.severity, conflict.describe(),
                 conflict.route1.file_path, conflict.route1.line_number,
-                conflict.route2.method, conflict.route2.path,
                 conflict.route2.file_path, conflict.route2.line_number
+            )).collect();
+
+            xml.push_str(&format!("    <testcase {}>\n", attributes));
+            xml.push_str(&format!(
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                xml_escape(conflicts[0].conflict_type.as_str()),
+                xml_escape(&conflicts[0].describe()),
+                xml_escape(&details.join("\n"))
             ));
+            xml.push_str("    </testcase>\n");
         }
+
+        xml.push_str("  </testsuite>\n");
     }
-    
-    fs::write(path, markdown).unwrap();
-    println!("\nDetailed Markdown report saved to {}", path.display());
-}
\ No newline at end of file
+    xml.push_str("</testsuites>\n");
+
+    Ok(xml)
+}
+
+fn render_checkstyle(report: &AnalysisReport) -> Result<String, String> {
+    // Every file with routes is listed, and every conflict is reported at both of its
+    // routes so that it shows up in each file involved
+    let mut files: BTreeMap<&str, Vec<(&Route, &RouteConflict)>> = report.inventory.files
+        .iter()
+        .map(|file| (file.file_path.as_str(), Vec::new()))
+        .collect();
+    for conflict in &report.conflicts {
+        for route in [&conflict.route1, &conflict.route2] {
+            files.entry(route.file_path.as_str()).or_default().push((route, conflict));
+        }
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<checkstyle version=\"4.3\">\n");
+
+    for (file_path, mut entries) in files {
+        entries.sort_by_key(|(route, _)| (route.line_number, route.column));
+
+        if entries.is_empty() {
+            xml.push_str(&format!("  <file name=\"{}\"/>\n", xml_escape(file_path)));
+            continue;
+        }
+
+        xml.push_str(&format!("  <file name=\"{}\">\n", xml_escape(file_path)));
+        for (route, conflict) in entries {
+            xml.push_str(&format!(
+                "    <error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"insidious.{}\"/>\n",
+                route.line_number,
+                route.column,
+                conflict.severity,
+                xml_escape(&conflict.describe()),
+                conflict.conflict_type.rule_name()
+            ));
+        }
+        xml.push_str("  </file>\n");
+    }
+    xml.push_str("</checkstyle>\n");
+
+    Ok(xml)
+}
+
+/// Escape text for use in XML content and attribute values
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_cells_escape_pipes_and_line_breaks() {
+        assert_eq!(markdown_cell("/:id(a|b)"), "/:id(a\\|b)");
+        assert_eq!(markdown_cell("auth,\nlog"), "auth, log");
+    }
+
+    fn conflict(conflict_type: ConflictType) -> RouteConflict {
+        let mut route2 = Route::for_tests("GET", "/users/me", 1);
+        route2.file_path = "project/routes/users.js".to_string();
+        route2.column = 5;
+
+        RouteConflict {
+            route1: Route::for_tests("GET", "/users/:id", 0),
+            route2,
+            similarity: 90.0,
+            conflict_type,
+            severity: conflict_type.severity(),
+            sample_url: Some("/users/me".to_string()),
+        }
+    }
+
+    #[test]
+    fn sarif_results_refer_to_their_rule_and_severity() {
+        for (index, conflict_type) in SARIF_RULES.iter().enumerate() {
+            let result = sarif_result(&conflict(*conflict_type));
+            assert_eq!(result["ruleId"], conflict_type.rule_name());
+            assert_eq!(result["ruleIndex"], index);
+            assert_eq!(result["level"], conflict_type.severity().as_str());
+        }
+
+        assert_eq!(sarif_result(&conflict(ConflictType::ShadowedRoute))["level"], "error");
+        assert_eq!(sarif_result(&conflict(ConflictType::ParameterConflict))["level"], "warning");
+    }
+
+    #[test]
+    fn sarif_results_are_located_at_the_first_route() {
+        let result = sarif_result(&conflict(ConflictType::ShadowedRoute));
+
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "project/app.js");
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 1);
+
+        let related = &result["relatedLocations"][0];
+        assert_eq!(related["physicalLocation"]["artifactLocation"]["uri"], "project/routes/users.js");
+        assert_eq!(related["physicalLocation"]["region"]["startLine"], 2);
+        assert_eq!(related["physicalLocation"]["region"]["startColumn"], 5);
+        assert_eq!(related["message"]["text"], "GET /users/me");
+    }
+
+    #[test]
+    fn sarif_uris_use_forward_slashes() {
+        assert_eq!(sarif_uri("./routes/users.js"), "routes/users.js");
+        assert_eq!(sarif_uri("routes\\users.js"), "routes/users.js");
+        assert_eq!(sarif_uri("/srv/app/app.js"), "file:///srv/app/app.js");
+    }
+}
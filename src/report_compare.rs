@@ -0,0 +1,250 @@
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use crate::baseline::fingerprint;
+use crate::models::{Route, RouteConflict, REPORT_SCHEMA_VERSION};
+use crate::output::{conflict_line, relative_file, route_key, route_line, KeyMatching, Section, SectionedOutput};
+
+/// The parts of a saved JSON report needed to compare it with another
+#[derive(Deserialize)]
+pub struct SavedReport {
+    /// Reports written before the schema was versioned lack the field and use version 1
+    #[serde(default = "unversioned_schema")]
+    pub schema_version: u32,
+    /// Missing from unversioned reports, whose file paths are then compared as written
+    #[serde(default)]
+    pub project_directory: String,
+    /// Missing from reports written before the route inventory existed
+    pub routes: Option<Vec<Route>>,
+    pub conflicts: Vec<RouteConflict>,
+}
+
+/// What appeared, disappeared or moved between two saved reports
+#[derive(Serialize)]
+pub struct ReportComparison {
+    pub old: String,
+    pub new: String,
+    pub appeared_routes: Vec<Route>,
+    pub disappeared_routes: Vec<Route>,
+    pub moved_routes: Vec<Moved<Route>>,
+    pub appeared_conflicts: Vec<RouteConflict>,
+    pub disappeared_conflicts: Vec<RouteConflict>,
+    pub moved_conflicts: Vec<Moved<RouteConflict>>,
+}
+
+/// An item found in both reports at different locations
+#[derive(Serialize)]
+pub struct Moved<T> {
+    pub before: T,
+    pub after: T,
+}
+
+fn unversioned_schema() -> u32 {
+    1
+}
+
+impl SavedReport {
+    pub fn load(path: &Path) -> Result<SavedReport, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Error: Could not read report '{}': {}", path.display(), err))?;
+        let report: SavedReport = serde_json::from_str(&json)
+            .map_err(|err| format!("Error: Invalid report '{}': {}", path.display(), err))?;
+
+        if report.schema_version > REPORT_SCHEMA_VERSION {
+            return Err(format!(
+                "Error: Report '{}' uses schema version {}, but this version of insidious reads up to {}",
+                path.display(), report.schema_version, REPORT_SCHEMA_VERSION
+            ));
+        }
+        if report.routes.is_none() {
+            return Err(format!(
+                "Error: Report '{}' has no route list; analyze the project again to create a report that does",
+                path.display()
+            ));
+        }
+
+        Ok(report)
+    }
+
+    fn routes(&self) -> &[Route] {
+        self.routes.as_deref().unwrap_or_default()
+    }
+}
+
+impl ReportComparison {
+    /// Match routes by method and full path, and conflicts by their baseline fingerprint.
+    /// Matched items whose file or line differs have moved.
+    pub fn new(old_name: &str, old: &SavedReport, new_name: &str, new: &SavedReport) -> ReportComparison {
+        let old_keys: Vec<_> = old.routes().iter().map(route_key).collect();
+        let new_keys: Vec<_> = new.routes().iter().map(route_key).collect();
+        let routes = KeyMatching::new(&old_keys, &new_keys);
+
+        let old_keys: Vec<_> = old.conflicts.iter().map(|conflict| fingerprint(conflict, &old.project_directory)).collect();
+        let new_keys: Vec<_> = new.conflicts.iter().map(|conflict| fingerprint(conflict, &new.project_directory)).collect();
+        let conflicts = KeyMatching::new(&old_keys, &new_keys);
+
+        let route_location = |route: &Route, report: &SavedReport| {
+            (relative_file(route, &report.project_directory), route.line_number)
+        };
+        let moved_routes = routes.pairs
+            .iter()
+            .map(|&(before, after)| (&old.routes()[before], &new.routes()[after]))
+            .filter(|(before, after)| route_location(before, old) != route_location(after, new))
+            .map(|(before, after)| Moved { before: before.clone(), after: after.clone() })
+            .collect();
+
+        // The fingerprint already covers the files, so only line numbers can differ
+        let moved_conflicts = conflicts.pairs
+            .iter()
+            .map(|&(before, after)| (&old.conflicts[before], &new.conflicts[after]))
+            .filter(|(before, after)| {
+                (before.route1.line_number, before.route2.line_number)
+                    != (after.route1.line_number, after.route2.line_number)
+            })
+            .map(|(before, after)| Moved { before: before.clone(), after: after.clone() })
+            .collect();
+
+        ReportComparison {
+            old: old_name.to_string(),
+            new: new_name.to_string(),
+            appeared_routes: routes.added.iter().map(|&index| new.routes()[index].clone()).collect(),
+            disappeared_routes: routes.removed.iter().map(|&index| old.routes()[index].clone()).collect(),
+            moved_routes,
+            appeared_conflicts: conflicts.added.iter().map(|&index| new.conflicts[index].clone()).collect(),
+            disappeared_conflicts: conflicts.removed.iter().map(|&index| old.conflicts[index].clone()).collect(),
+            moved_conflicts,
+        }
+    }
+}
+
+impl SectionedOutput for ReportComparison {
+    fn heading(&self, markdown: bool) -> String {
+        let quote = if markdown { "`" } else { "" };
+        format!("Comparing {q}{}{q} with {q}{}{q}", self.old, self.new, q = quote)
+    }
+
+    fn summary_table(&self) -> String {
+        let mut md = String::new();
+        md.push_str("| | Appeared | Disappeared | Moved |\n");
+        md.push_str("|-|----------|-------------|-------|\n");
+        md.push_str(&format!(
+            "| Routes | {} | {} | {} |\n",
+            self.appeared_routes.len(), self.disappeared_routes.len(), self.moved_routes.len()
+        ));
+        md.push_str(&format!(
+            "| Conflicts | {} | {} | {} |\n",
+            self.appeared_conflicts.len(), self.disappeared_conflicts.len(), self.moved_conflicts.len()
+        ));
+        md
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        vec![
+            Section { title: "Appeared Routes", marker: '+', lines: self.appeared_routes.iter().map(route_line).collect() },
+            Section {
+                title: "Disappeared Routes",
+                marker: '-',
+                lines: self.disappeared_routes.iter().map(route_line).collect(),
+            },
+            Section {
+                title: "Moved Routes",
+                marker: '~',
+                lines: self.moved_routes.iter()
+                    .map(|moved| format!(
+                        "{} {}: {}:{} -> {}:{}",
+                        moved.after.method, moved.after.path,
+                        moved.before.file_path, moved.before.line_number,
+                        moved.after.file_path, moved.after.line_number
+                    ))
+                    .collect(),
+            },
+            Section {
+                title: "Appeared Conflicts",
+                marker: '+',
+                lines: self.appeared_conflicts.iter().map(conflict_line).collect(),
+            },
+            Section {
+                title: "Disappeared Conflicts",
+                marker: '-',
+                lines: self.disappeared_conflicts.iter().map(conflict_line).collect(),
+            },
+            Section {
+                title: "Moved Conflicts",
+                marker: '~',
+                lines: self.moved_conflicts.iter().map(|moved| conflict_line(&moved.after)).collect(),
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConflictType, Severity};
+
+    /// A route in `app.js` of the given project directory, at the given line
+    fn route(project_directory: &str, method: &str, path: &str, line: usize) -> Route {
+        let mut route = Route::for_tests(method, path, line - 1);
+        route.file_path = format!("{}/app.js", project_directory);
+        route
+    }
+
+    fn report(project_directory: &str, routes: Vec<Route>) -> SavedReport {
+        let conflicts = routes.windows(2)
+            .map(|pair| RouteConflict {
+                route1: pair[0].clone(),
+                route2: pair[1].clone(),
+                similarity: 80.0,
+                conflict_type: ConflictType::ShadowedRoute,
+                severity: Severity::Error,
+                sample_url: None,
+            })
+            .collect();
+
+        SavedReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            project_directory: project_directory.to_string(),
+            routes: Some(routes),
+            conflicts,
+        }
+    }
+
+    #[test]
+    fn routes_match_by_method_and_path_in_project_relative_files() {
+        // The same project checked out in two places
+        let old = report("/ci/one", vec![route("/ci/one", "GET", "/users/:id", 1), route("/ci/one", "GET", "/users/me", 2)]);
+        let new = report("/ci/two", vec![route("/ci/two", "GET", "/users/:id", 1), route("/ci/two", "GET", "/users/me", 2)]);
+
+        let comparison = ReportComparison::new("old.json", &old, "new.json", &new);
+        assert!(comparison.appeared_routes.is_empty() && comparison.disappeared_routes.is_empty());
+        assert!(comparison.moved_routes.is_empty());
+        assert!(comparison.appeared_conflicts.is_empty() && comparison.disappeared_conflicts.is_empty());
+        assert!(comparison.moved_conflicts.is_empty());
+    }
+
+    #[test]
+    fn routes_and_conflicts_found_at_other_lines_have_moved() {
+        let old = report("project", vec![route("project", "GET", "/users/:id", 1), route("project", "GET", "/users/me", 2)]);
+        let new = report("project", vec![route("project", "GET", "/users/:id", 5), route("project", "GET", "/users/me", 9)]);
+
+        let comparison = ReportComparison::new("old.json", &old, "new.json", &new);
+        assert_eq!(comparison.moved_routes.len(), 2);
+        assert_eq!(comparison.moved_routes[1].before.line_number, 2);
+        assert_eq!(comparison.moved_routes[1].after.line_number, 9);
+        assert_eq!(comparison.moved_conflicts.len(), 1);
+        assert!(comparison.appeared_conflicts.is_empty() && comparison.disappeared_conflicts.is_empty());
+    }
+
+    #[test]
+    fn a_different_method_or_path_is_a_different_route() {
+        let old = report("project", vec![route("project", "GET", "/users/:id", 1), route("project", "GET", "/users/me", 2)]);
+        let new = report("project", vec![route("project", "GET", "/users/:userId", 1), route("project", "PUT", "/users/me", 2)]);
+
+        let comparison = ReportComparison::new("old.json", &old, "new.json", &new);
+        assert_eq!(comparison.appeared_routes.len(), 2);
+        assert_eq!(comparison.disappeared_routes.len(), 2);
+        assert!(comparison.moved_routes.is_empty());
+        assert_eq!(comparison.appeared_conflicts.len(), 1);
+        assert_eq!(comparison.disappeared_conflicts.len(), 1);
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+use crate::baseline::conflict_changes;
+use crate::models::{Route, RouteConflict};
+use crate::output::{conflict_line, route_key, route_line, KeyMatching, Section, SectionedOutput};
+
+/// How the routes and conflicts of a project differ between two revisions
+#[derive(Serialize)]
+pub struct RouteDiff {
+    pub base: String,
+    pub head: String,
+    pub added_routes: Vec<Route>,
+    pub removed_routes: Vec<Route>,
+    pub changed_routes: Vec<RouteChange>,
+    pub introduced_conflicts: Vec<RouteConflict>,
+    pub resolved_conflicts: Vec<RouteConflict>,
+}
+
+/// A route registered in both revisions whose file, router or mount chain differs
+#[derive(Serialize)]
+pub struct RouteChange {
+    pub before: Route,
+    pub after: Route,
+    /// What changed, e.g. `file: app.js -> routes/users.js`
+    pub changes: Vec<String>,
+}
+
+impl RouteDiff {
+    /// Compare the routes and conflicts of the base and head revisions.
+    ///
+    /// Routes are matched by method and full path; moving a route to another line of the
+    /// same file is not a change.
+    pub fn new(
+        base: &str,
+        head: &str,
+        (base_routes, base_conflicts): (&[Route], &[RouteConflict]),
+        (head_routes, head_conflicts): (&[Route], &[RouteConflict]),
+        project_directory: &str,
+    ) -> RouteDiff {
+        let base_keys: Vec<_> = base_routes.iter().map(route_key).collect();
+        let head_keys: Vec<_> = head_routes.iter().map(route_key).collect();
+        let matching = KeyMatching::new(&base_keys, &head_keys);
+
+        let changed_routes = matching.pairs
+            .iter()
+            .filter_map(|&(before, after)| {
+                let (before, after) = (&base_routes[before], &head_routes[after]);
+                let changes = route_changes(before, after);
+                (!changes.is_empty()).then(|| RouteChange { before: before.clone(), after: after.clone(), changes })
+            })
+            .collect();
+        let added_routes = matching.added.iter().map(|&index| head_routes[index].clone()).collect();
+        let removed_routes = matching.removed.iter().map(|&index| base_routes[index].clone()).collect();
+
+        let (introduced, resolved) = conflict_changes(base_conflicts, head_conflicts, project_directory);
+
+        RouteDiff {
+            base: base.to_string(),
+            head: head.to_string(),
+            added_routes,
+            removed_routes,
+            changed_routes,
+            introduced_conflicts: introduced.into_iter().cloned().collect(),
+            resolved_conflicts: resolved.into_iter().cloned().collect(),
+        }
+    }
+}
+
+impl SectionedOutput for RouteDiff {
+    fn heading(&self, markdown: bool) -> String {
+        let quote = if markdown { "`" } else { "" };
+        format!("Route changes from {q}{}{q} to {q}{}{q}", self.base, self.head, q = quote)
+    }
+
+    fn summary_table(&self) -> String {
+        let mut md = String::new();
+        md.push_str("| Added | Removed | Changed | Conflicts Introduced | Conflicts Resolved |\n");
+        md.push_str("|-------|---------|---------|----------------------|--------------------|\n");
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            self.added_routes.len(),
+            self.removed_routes.len(),
+            self.changed_routes.len(),
+            self.introduced_conflicts.len(),
+            self.resolved_conflicts.len()
+        ));
+        md
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        vec![
+            Section { title: "Added Routes", marker: '+', lines: self.added_routes.iter().map(route_line).collect() },
+            Section { title: "Removed Routes", marker: '-', lines: self.removed_routes.iter().map(route_line).collect() },
+            Section {
+                title: "Changed Routes",
+                marker: '~',
+                lines: self.changed_routes.iter()
+                    .map(|change| format!("{} {}: {}", change.after.method, change.after.path, change.changes.join("; ")))
+                    .collect(),
+            },
+            Section {
+                title: "Conflicts Introduced",
+                marker: '+',
+                lines: self.introduced_conflicts.iter().map(conflict_line).collect(),
+            },
+            Section {
+                title: "Conflicts Resolved",
+                marker: '-',
+                lines: self.resolved_conflicts.iter().map(conflict_line).collect(),
+            },
+        ]
+    }
+}
+
+fn route_changes(before: &Route, after: &Route) -> Vec<String> {
+    let mut changes = Vec::new();
+    if before.file_path != after.file_path {
+        changes.push(format!("file: {} -> {}", before.file_path, after.file_path));
+    }
+    if before.router != after.router {
+        changes.push(format!("router: {} -> {}", before.router, after.router));
+    }
+
+    let mounts = |route: &Route| route.mount_description().unwrap_or_else(|| "none".to_string());
+    if mounts(before) != mounts(after) {
+        changes.push(format!("mounted via: {} -> {}", mounts(before), mounts(after)));
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MountPoint;
+
+    fn diff(base_routes: &[Route], head_routes: &[Route]) -> RouteDiff {
+        RouteDiff::new("main", "HEAD", (base_routes, &[]), (head_routes, &[]), "project")
+    }
+
+    fn keys(routes: &[Route]) -> Vec<(String, String)> {
+        routes.iter().map(route_key).collect()
+    }
+
+    #[test]
+    fn routes_are_matched_by_method_and_path() {
+        let base = [Route::for_tests("GET", "/users", 0), Route::for_tests("POST", "/users", 1)];
+        let head = [Route::for_tests("GET", "/users", 0), Route::for_tests("GET", "/users/:id", 1)];
+
+        let diff = diff(&base, &head);
+        assert_eq!(keys(&diff.added_routes), [("GET".to_string(), "/users/:id".to_string())]);
+        assert_eq!(keys(&diff.removed_routes), [("POST".to_string(), "/users".to_string())]);
+        assert!(diff.changed_routes.is_empty());
+    }
+
+    #[test]
+    fn moving_a_route_within_its_file_is_not_a_change() {
+        let base = [Route::for_tests("GET", "/users", 0)];
+        let head = [Route::for_tests("GET", "/health", 0), Route::for_tests("GET", "/users", 5)];
+
+        let diff = diff(&base, &head);
+        assert_eq!(keys(&diff.added_routes), [("GET".to_string(), "/health".to_string())]);
+        assert!(diff.removed_routes.is_empty());
+        assert!(diff.changed_routes.is_empty());
+    }
+
+    #[test]
+    fn moving_a_route_to_another_file_router_or_mount_is_a_change() {
+        let base = [Route::for_tests("GET", "/api/users", 0)];
+        let mut moved = Route::for_tests("GET", "/api/users", 0);
+        moved.file_path = "project/routes/users.js".to_string();
+        moved.router = "router".to_string();
+        moved.mount_chain.push(MountPoint { prefix: "/api".to_string(), file_path: "project/app.js".to_string(), line_number: 3 });
+
+        let diff = diff(&base, &[moved]);
+        assert!(diff.added_routes.is_empty() && diff.removed_routes.is_empty());
+        assert_eq!(diff.changed_routes.len(), 1);
+        assert_eq!(diff.changed_routes[0].changes, [
+            "file: project/app.js -> project/routes/users.js",
+            "router: app -> router",
+            "mounted via: none -> /api",
+        ]);
+    }
+
+    #[test]
+    fn duplicate_routes_are_matched_in_order() {
+        let base = [Route::for_tests("GET", "/users", 0), Route::for_tests("GET", "/users", 1)];
+        let head = [Route::for_tests("GET", "/users", 0)];
+
+        let matching = KeyMatching::new(&keys(&base), &keys(&head));
+        assert_eq!(matching.pairs, [(0, 0)]);
+        assert_eq!(matching.removed, [1]);
+        assert!(matching.added.is_empty());
+
+        let diff = diff(&base, &head);
+        assert_eq!(diff.removed_routes.len(), 1);
+        assert_eq!(diff.removed_routes[0].line_number, 2);
+    }
+}
@@ -1,72 +1,766 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+use rayon::prelude::*;
 use walkdir::WalkDir;
-use crate::models::Route;
+use crate::js_parser::{self, UseTarget};
+use crate::models::{Middleware, MountPoint, Route, SourceSnippet, Suppression};
 use crate::cli::AnalysisConfig;
+use crate::scan_cache::{CacheEntry, ScanCache};
+use crate::utils::{join_route_paths, normalize_path};
 
-pub fn extract_all_routes(config: &AnalysisConfig) -> Vec<Route> {
-    let mut all_routes = Vec::new();
-    
-    for entry in WalkDir::new(&config.project_directory)
+/// A router instance, identified by the file it lives in and its binding name
+type RouterId = (PathBuf, String);
+
+/// Everything the extractor learned about a single source file
+struct FileScan {
+    file_path: String,
+    /// Routes registered in the file, keyed by the receiver they were registered on
+    routes: Vec<(String, Route)>,
+    uses: Vec<UseCall>,
+    /// Bindings holding an app or router instance
+    routers: HashSet<String>,
+    /// Local bindings that refer to other modules (`const users = require('./users')`)
+    imports: HashMap<String, PathBuf>,
+    /// Binding exported as the module's default export
+    export: Option<String>,
+    suppressions: Vec<Suppression>,
+}
+
+/// Routes found in a project, with every suppression comment in its files
+pub struct Extraction {
+    pub routes: Vec<Route>,
+    pub suppressions: Vec<Suppression>,
+}
+
+/// One argument of a `receiver.use(prefix, ...)` call found in a file
+struct UseCall {
+    receiver: String,
+    prefix: String,
+    name: String,
+    /// The module or binding the argument refers to, when it may be a router
+    target: Option<MountTarget>,
+    line_number: usize,
+    column: usize,
+}
+
+enum MountTarget {
+    Binding(String),
+    Module(PathBuf),
+}
+
+/// Where an item is registered on a router: whether it is in another file than the router's
+/// own, that file, and the line and column
+type ItemPosition<'a> = (bool, &'a Path, usize, usize);
+
+/// An item registered on a router, in source order
+enum RouterItem<'a> {
+    Route(&'a Route),
+    Mount(&'a UseCall, RouterId),
+    Middleware(&'a UseCall),
+}
+
+/// Where a project's files are read from
+pub trait SourceTree: Sync {
+    /// Source files at or below `root`, skipping hidden and excluded directories
+    fn source_files(&self, root: &Path, config: &AnalysisConfig) -> Vec<PathBuf>;
+    fn read_to_string(&self, path: &Path) -> Option<String>;
+    fn is_file(&self, path: &Path) -> bool;
+}
+
+/// The project's files on disk
+pub struct FileSystem;
+
+/// The scanned source files of a project, kept so that changed files can be scanned again
+pub struct ProjectScan {
+    scans: HashMap<PathBuf, FileScan>,
+}
+
+pub fn extract_all_routes(config: &AnalysisConfig) -> Extraction {
+    ProjectScan::new(config).extraction()
+}
+
+impl ProjectScan {
+    /// Scan every source file in the project, reusing cached parse results when enabled
+    pub fn new(config: &AnalysisConfig) -> ProjectScan {
+        Self::scan(&FileSystem, config, config.cache)
+    }
+
+    /// Scan every source file in a tree other than the working directory, without the cache
+    pub fn from_tree(tree: &dyn SourceTree, config: &AnalysisConfig) -> ProjectScan {
+        Self::scan(tree, config, false)
+    }
+
+    fn scan(tree: &dyn SourceTree, config: &AnalysisConfig, use_cache: bool) -> ProjectScan {
+        let files = tree.source_files(Path::new(&config.project_directory), config);
+
+        let cache_path = use_cache.then(|| ScanCache::path(&config.project_directory));
+        let mut cache = cache_path.as_deref().map(ScanCache::load);
+
+        let results: Vec<(PathBuf, String, FileScan, Option<CacheEntry>)> = files
+            .par_iter()
+            .map(|path| {
+                let key = cache_key(path, &config.project_directory);
+                let (scan, entry) = scan_file(tree, path, &key, config, cache.as_ref());
+                (normalize_path(path), key, scan, entry)
+            })
+            .collect();
+
+        let mut scans = HashMap::new();
+        let mut keys = HashSet::new();
+        let mut entries = Vec::new();
+        for (path, key, scan, entry) in results {
+            scans.insert(path, scan);
+            if let Some(entry) = entry {
+                entries.push((key.clone(), entry));
+            }
+            keys.insert(key);
+        }
+
+        if let (Some(cache), Some(path)) = (cache.as_mut(), &cache_path) {
+            if cache.update(&keys, entries) {
+                // A cache that cannot be written only costs time on the next run
+                if let Err(err) = cache.save(path) {
+                    eprintln!("Warning: {}", err);
+                }
+            }
+        }
+
+        ProjectScan { scans }
+    }
+
+    /// Bring the scan up to date after the given files or directories changed.
+    ///
+    /// Paths that no longer exist are forgotten along with everything below them, and
+    /// source files at or below the others are scanned again. Returns whether any source
+    /// file was affected.
+    pub fn update(&mut self, paths: &[PathBuf], config: &AnalysisConfig) -> bool {
+        let before = self.scans.len();
+        let mut files = Vec::new();
+
+        for path in paths {
+            let normalized = normalize_path(path);
+            self.scans.retain(|file, _| !file.starts_with(&normalized));
+
+            if !is_included(path, config) {
+                continue;
+            }
+            if path.is_dir() {
+                files.extend(source_files(path, config));
+            } else if path.is_file() && has_source_extension(path, config) {
+                files.push(path.clone());
+            }
+        }
+        files.sort();
+        files.dedup();
+
+        let removed = before != self.scans.len();
+        let results: Vec<(PathBuf, FileScan)> = files
+            .par_iter()
+            .map(|path| {
+                let key = cache_key(path, &config.project_directory);
+                (normalize_path(path), scan_file(&FileSystem, path, &key, config, None).0)
+            })
+            .collect();
+
+        let changed = removed || !results.is_empty();
+        self.scans.extend(results);
+        changed
+    }
+
+    /// The project's routes and suppression comments as of the last scan
+    pub fn extraction(&self) -> Extraction {
+        let mut suppressions: Vec<Suppression> = self.scans.values()
+            .flat_map(|scan| scan.suppressions.iter().cloned())
+            .collect();
+        suppressions.sort_by(|a, b| (&a.file_path, a.line_number).cmp(&(&b.file_path, b.line_number)));
+
+        Extraction {
+            routes: resolve_routes(&self.scans),
+            suppressions,
+        }
+    }
+}
+
+impl SourceTree for FileSystem {
+    fn source_files(&self, root: &Path, config: &AnalysisConfig) -> Vec<PathBuf> {
+        source_files(root, config)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+/// Source files on disk at or below `root`, skipping hidden and excluded directories
+fn source_files(root: &Path, config: &AnalysisConfig) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .sort_by_file_name()
         .into_iter()
         .filter_entry(|e| {
             // Filter out hidden files/dirs
-            if e.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false) {
+            if e.depth() > 0 && e.file_name().to_str().map(|s| s.starts_with('.')).unwrap_or(false) {
                 return false;
             }
-            
+
             // Filter out excluded directories
             let path = e.path();
             if path.is_dir() {
                 let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
                 return !config.excluded_dirs.iter().any(|excluded| excluded == dir_name);
             }
-            
+
             true
         })
-    {
-        if let Ok(entry) = entry {
-            if let Some(ext) = entry.path().extension() {
-                if let Some(ext_str) = ext.to_str() {
-                    if config.file_extensions.iter().any(|e| ext_str == e) {
-                        let file_path = entry.path().to_str().unwrap();
-                        let routes = extract_routes_from_file(file_path);
-                        all_routes.extend(routes);
-                    }
+        .flatten()
+        .filter(|entry| has_source_extension(entry.path(), config))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+pub fn has_source_extension(path: &Path, config: &AnalysisConfig) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| config.file_extensions.iter().any(|e| ext == e))
+}
+
+/// Whether a path inside the project lies outside every hidden and excluded directory
+pub fn is_included(path: &Path, config: &AnalysisConfig) -> bool {
+    let path = normalize_path(path);
+    let project_directory = normalize_path(Path::new(&config.project_directory));
+    let relative = path.strip_prefix(&project_directory).unwrap_or(&path);
+
+    relative.components().all(|component| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            !name.starts_with('.') && !config.excluded_dirs.iter().any(|excluded| *excluded == name)
+        },
+        _ => true,
+    })
+}
+
+/// Scan one file, parsing it only when `cache` has no up-to-date result for it.
+///
+/// Also returns the cache entry to record for the file, if it changed.
+fn scan_file(
+    tree: &dyn SourceTree,
+    path: &Path,
+    cache_key: &str,
+    config: &AnalysisConfig,
+    cache: Option<&ScanCache>,
+) -> (FileScan, Option<CacheEntry>) {
+    let extensions = &config.file_extensions;
+    let file_path = path.to_string_lossy();
+    let content = tree.read_to_string(path).unwrap_or_default();
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let (module, entry) = match cache {
+        Some(cache) => cache.scan(cache_key, &file_path, &content),
+        None => (js_parser::scan_module(&file_path, &content), None),
+    };
+
+    let comments = module.suppressions;
+    let suppressions: Vec<Suppression> = comments
+        .iter()
+        .map(|comment| Suppression {
+            kind: comment.kind,
+            rules: comment.rules.clone(),
+            file_path: file_path.to_string(),
+            line_number: comment.line_number,
+        })
+        .collect();
+
+    let routes = module.routes
+        .into_iter()
+        .map(|call| (call.receiver, Route {
+            suppressions: comments
+                .iter()
+                .zip(&suppressions)
+                .filter(|(comment, _)| (comment.first_line..=comment.last_line).contains(&call.line_number))
+                .map(|(_, suppression)| suppression.clone())
+                .collect(),
+            snippet: SourceSnippet::capture(&content, call.line_number, config.context_lines),
+            method: call.method,
+            path: call.path,
+            file_path: file_path.to_string(),
+            line_number: call.line_number,
+            column: call.column,
+            router: String::new(),
+            order: 0,
+            root: 0,
+            mount_chain: Vec::new(),
+            middleware: call.middleware
+                .into_iter()
+                .map(|name| Middleware {
+                    name,
+                    router: String::new(),
+                    prefix: None,
+                    file_path: file_path.to_string(),
+                    line_number: call.line_number,
+                })
+                .collect(),
+        }))
+        .collect();
+
+    let uses = module.uses
+        .into_iter()
+        .flat_map(|call| {
+            call.arguments.into_iter().map(move |argument| {
+                let target = argument.target.and_then(|target| match target {
+                    UseTarget::Binding(name) => Some(MountTarget::Binding(name)),
+                    UseTarget::Require(specifier) => {
+                        resolve_module(tree, base_dir, &specifier, extensions).map(MountTarget::Module)
+                    },
+                });
+
+                UseCall {
+                    receiver: call.receiver.clone(),
+                    prefix: call.prefix.clone(),
+                    name: argument.name,
+                    target,
+                    line_number: call.line_number,
+                    column: call.column,
                 }
-            }
+            })
+        })
+        .collect();
+
+    let imports = module.imports
+        .into_iter()
+        .filter_map(|(name, specifier)| Some((name, resolve_module(tree, base_dir, &specifier, extensions)?)))
+        .collect();
+
+    let scan = FileScan {
+        file_path: file_path.to_string(),
+        routes,
+        uses,
+        routers: module.routers,
+        imports,
+        export: module.export,
+        suppressions,
+    };
+    (scan, entry)
+}
+
+/// A file's path relative to the project directory, used to look it up in the cache
+fn cache_key(path: &Path, project_directory: &str) -> String {
+    let relative = path.strip_prefix(project_directory).unwrap_or(path);
+    normalize_path(relative).to_string_lossy().replace('\\', "/")
+}
+
+/// Resolve a relative module specifier the way Node does for local files
+fn resolve_module(tree: &dyn SourceTree, base_dir: &Path, specifier: &str, extensions: &[String]) -> Option<PathBuf> {
+    if !specifier.starts_with('.') {
+        return None;
+    }
+
+    let candidate = base_dir.join(specifier);
+    if tree.is_file(&candidate) {
+        return Some(normalize_path(&candidate));
+    }
+
+    for ext in extensions {
+        let with_ext = PathBuf::from(format!("{}.{}", candidate.display(), ext));
+        if tree.is_file(&with_ext) {
+            return Some(normalize_path(&with_ext));
+        }
+
+        let index = candidate.join(format!("index.{}", ext));
+        if tree.is_file(&index) {
+            return Some(normalize_path(&index));
         }
     }
-    
-    all_routes
+
+    None
 }
 
-fn extract_routes_from_file(file_path: &str) -> Vec<Route> {
-    let content = fs::read_to_string(file_path).unwrap_or_default();
-    let mut routes = Vec::new();
-    
-    let app_route_regex = Regex::new(r#"app\.(get|post|put|delete|patch)\(['"](.+?)['"],?"#).unwrap();
-    let router_route_regex = Regex::new(r#"router\.(get|post|put|delete|patch)\(['"](.+?)['"],?"#).unwrap();
-    
-    for (line_number, line) in content.lines().enumerate() {
-        for cap in app_route_regex.captures_iter(line) {
-            routes.push(Route {
-                method: cap[1].to_uppercase(),
-                path: cap[2].to_string(),
-                file_path: file_path.to_string(),
-                line_number: line_number + 1,
-            });
+/// Follow mount calls across files and give every route its fully resolved path
+fn resolve_routes(scans: &HashMap<PathBuf, FileScan>) -> Vec<Route> {
+    let mut items: HashMap<RouterId, Vec<(ItemPosition, RouterItem)>> = HashMap::new();
+    let mut mounted = HashSet::new();
+
+    for (file, scan) in scans {
+        for (receiver, route) in &scan.routes {
+            let Some(router) = resolve_binding(file, scan, receiver, scans) else {
+                continue;
+            };
+            if !is_router(&router, scans) {
+                continue;
+            }
+
+            let position = (*file != router.0, file.as_path(), route.line_number, route.column);
+            items.entry(router)
+                .or_default()
+                .push((position, RouterItem::Route(route)));
         }
-        
-        for cap in router_route_regex.captures_iter(line) {
-            routes.push(Route {
-                method: cap[1].to_uppercase(),
-                path: cap[2].to_string(),
-                file_path: file_path.to_string(),
-                line_number: line_number + 1,
+
+        for call in &scan.uses {
+            let Some(router) = resolve_binding(file, scan, &call.receiver, scans) else {
+                continue;
+            };
+            if !is_router(&router, scans) {
+                continue;
+            }
+
+            // Arguments that are not routers are middleware
+            let child = call.target.as_ref().and_then(|target| match target {
+                MountTarget::Binding(name) => resolve_binding(file, scan, name, scans),
+                MountTarget::Module(module) => exported_router(module, scans),
             });
+            let item = match child {
+                Some(child) if is_router(&child, scans) => {
+                    mounted.insert(child.clone());
+                    RouterItem::Mount(call, child)
+                },
+                _ => RouterItem::Middleware(call),
+            };
+            let position = (*file != router.0, file.as_path(), call.line_number, call.column);
+            items.entry(router).or_default().push((position, item));
         }
     }
-    
+
+    // Registration order: the router's own file first, then other files by path; within a
+    // file by source position. The sort is stable, so the arguments of one `use()` keep their order.
+    for router_items in items.values_mut() {
+        router_items.sort_by_key(|(position, _)| *position);
+    }
+
+    let mut roots: Vec<&RouterId> = items.keys().filter(|id| !mounted.contains(*id)).collect();
+    roots.sort();
+
+    // Each root is a dispatch tree of its own, so the order between roots means nothing
+    let mut routes = Vec::new();
+    let mut visited = HashSet::new();
+    for (root, id) in roots.iter().enumerate() {
+        collect_routes(id, root, "/", &mut Vec::new(), &mut Vec::new(), &mut Vec::new(), &items, scans, &mut visited, &mut routes);
+    }
+
+    // Routers that are only reachable through a mount cycle are reported unprefixed
+    let mut unreached: Vec<&RouterId> = items.keys().filter(|id| !visited.contains(*id)).collect();
+    unreached.sort();
+    let mut root = roots.len();
+    for id in unreached {
+        if !visited.contains(id) {
+            collect_routes(id, root, "/", &mut Vec::new(), &mut Vec::new(), &mut Vec::new(), &items, scans, &mut visited, &mut routes);
+            root += 1;
+        }
+    }
+
     routes
-}
\ No newline at end of file
+}
+
+/// Resolve a binding used in `file` to the router it refers to, following imports
+fn resolve_binding(
+    file: &Path,
+    scan: &FileScan,
+    name: &str,
+    scans: &HashMap<PathBuf, FileScan>,
+) -> Option<RouterId> {
+    match scan.imports.get(name) {
+        Some(module) => exported_router(module, scans),
+        None => Some((file.to_path_buf(), name.to_string())),
+    }
+}
+
+/// The router a module exports. Without a recognizable export, a module that creates a
+/// single router is assumed to export it.
+fn exported_router(module: &Path, scans: &HashMap<PathBuf, FileScan>) -> Option<RouterId> {
+    let scan = scans.get(module)?;
+    let exported = match &scan.export {
+        Some(export) => export.clone(),
+        None if scan.routers.len() == 1 => scan.routers.iter().next()?.clone(),
+        None => "router".to_string(),
+    };
+
+    Some((module.to_path_buf(), exported))
+}
+
+/// Whether a router id refers to a binding known to hold an app or router instance
+fn is_router(id: &RouterId, scans: &HashMap<PathBuf, FileScan>) -> bool {
+    scans.get(&id.0).is_some_and(|scan| scan.routers.contains(&id.1))
+}
+
+/// Walk a router's routes and mounts in registration order, as part of the dispatch tree `root`.
+///
+/// `middleware` holds the middleware registered so far on the way to this router, each
+/// with the full path it applies under.
+#[allow(clippy::too_many_arguments)]
+fn collect_routes(
+    id: &RouterId,
+    root: usize,
+    prefix: &str,
+    chain: &mut Vec<MountPoint>,
+    middleware: &mut Vec<(String, Middleware)>,
+    stack: &mut Vec<RouterId>,
+    items: &HashMap<RouterId, Vec<(ItemPosition, RouterItem)>>,
+    scans: &HashMap<PathBuf, FileScan>,
+    visited: &mut HashSet<RouterId>,
+    routes: &mut Vec<Route>,
+) {
+    // Guard against routers that (indirectly) mount themselves
+    if stack.contains(id) {
+        return;
+    }
+    visited.insert(id.clone());
+
+    let Some(router_items) = items.get(id) else {
+        return;
+    };
+
+    stack.push(id.clone());
+    let inherited = middleware.len();
+    for (_, item) in router_items {
+        match item {
+            RouterItem::Route(route) => {
+                let mut route = (*route).clone();
+                route.path = join_route_paths(prefix, &route.path);
+                route.mount_chain = chain.clone();
+                route.router = id.1.clone();
+                route.order = routes.len();
+                route.root = root;
+
+                let inline = std::mem::take(&mut route.middleware);
+                route.middleware = middleware
+                    .iter()
+                    .filter(|(path, _)| applies_to(path, &route.path))
+                    .map(|(_, middleware)| middleware.clone())
+                    .chain(inline.into_iter().map(|middleware| Middleware { router: id.1.clone(), ..middleware }))
+                    .collect();
+                routes.push(route);
+            },
+            RouterItem::Middleware(call) => {
+                middleware.push((join_route_paths(prefix, &call.prefix), Middleware {
+                    name: call.name.clone(),
+                    router: id.1.clone(),
+                    prefix: Some(call.prefix.clone()),
+                    file_path: scans[&id.0].file_path.clone(),
+                    line_number: call.line_number,
+                }));
+            },
+            RouterItem::Mount(mount, child) => {
+                chain.push(MountPoint {
+                    prefix: mount.prefix.clone(),
+                    file_path: scans[&id.0].file_path.clone(),
+                    line_number: mount.line_number,
+                });
+                let child_prefix = join_route_paths(prefix, &mount.prefix);
+                collect_routes(child, root, &child_prefix, chain, middleware, stack, items, scans, visited, routes);
+                chain.pop();
+            },
+        }
+    }
+    // Middleware a router registers runs only for requests that reach it
+    middleware.truncate(inherited);
+    stack.pop();
+}
+
+/// Whether `use()` middleware registered for `prefix` runs for requests to `path`: each
+/// segment of the prefix must match the path's segment, where a parameter matches anything
+fn applies_to(prefix: &str, path: &str) -> bool {
+    let path_segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let prefix_segments: Vec<&str> = prefix.split('/').filter(|segment| !segment.is_empty()).collect();
+
+    prefix_segments.len() <= path_segments.len()
+        && prefix_segments.iter().zip(&path_segments).all(|(prefix, path)| {
+            prefix.starts_with(':') || path.starts_with(':') || prefix.eq_ignore_ascii_case(path)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict_analyzer::check_route_conflicts;
+    use crate::models::ConflictType;
+
+    /// Source files held in memory, keyed by their path under the project directory
+    struct MemoryTree(HashMap<PathBuf, String>);
+
+    impl MemoryTree {
+        fn new(files: &[(&str, &str)]) -> MemoryTree {
+            MemoryTree(files.iter().map(|(path, source)| (Path::new("project").join(path), source.to_string())).collect())
+        }
+    }
+
+    impl SourceTree for MemoryTree {
+        fn source_files(&self, _root: &Path, _config: &AnalysisConfig) -> Vec<PathBuf> {
+            let mut files: Vec<PathBuf> = self.0.keys().cloned().collect();
+            files.sort();
+            files
+        }
+
+        fn read_to_string(&self, path: &Path) -> Option<String> {
+            self.0.get(&normalize_path(path)).cloned()
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            self.0.contains_key(&normalize_path(path))
+        }
+    }
+
+    fn extract(files: &[(&str, &str)]) -> Vec<Route> {
+        let mut routes = ProjectScan::from_tree(&MemoryTree::new(files), &AnalysisConfig::for_tests()).extraction().routes;
+        routes.sort_by_key(|route| route.order);
+        routes
+    }
+
+    #[test]
+    fn route_chain_all_shadows_later_get() {
+        let routes = extract(&[("app.js", "const app = require('express')();\napp.route('/x').all(h).get(h2);")]);
+        let analysis = check_route_conflicts(&routes, &[], &AnalysisConfig::for_tests());
+
+        assert_eq!(routes.iter().map(|route| route.method.as_str()).collect::<Vec<_>>(), ["ALL", "GET"]);
+        assert_eq!(analysis.conflicts.len(), 1);
+        assert_eq!(analysis.conflicts[0].conflict_type, ConflictType::ShadowedRoute);
+        assert_eq!(analysis.conflicts[0].route2.method, "GET");
+    }
+
+    #[test]
+    fn registrations_on_one_line_keep_their_source_order() {
+        let routes = extract(&[(
+            "app.js",
+            "const app = require('express')();\napp.get('/a', h); app.use(auth); app.get('/b', h);",
+        )]);
+
+        assert_eq!(routes.iter().map(|route| route.path.as_str()).collect::<Vec<_>>(), ["/a", "/b"]);
+        assert!(routes[0].middleware.is_empty());
+        assert_eq!(routes[1].middleware.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["auth"]);
+    }
+
+    #[test]
+    fn every_suppression_covering_a_conflict_is_used() {
+        let extraction = ProjectScan::from_tree(&MemoryTree::new(&[(
+            "app.js",
+            "const app = require('express')();\n\
+             // insidious-ignore-next-line exact-match\napp.get('/a', h);\n\
+             // insidious-ignore-next-line\napp.get('/a', h);",
+        )]), &AnalysisConfig::for_tests()).extraction();
+        let analysis = check_route_conflicts(&extraction.routes, &extraction.suppressions, &AnalysisConfig::for_tests());
+
+        assert!(analysis.conflicts.is_empty());
+        assert_eq!(analysis.suppressed.len(), 1);
+        assert!(analysis.unused_suppressions.is_empty());
+    }
+
+    #[test]
+    fn separate_apps_never_shadow_each_other() {
+        let routes = extract(&[
+            ("app.js", "const app = require('express')();\napp.get('/users/:id', h);"),
+            ("admin/server.js", "const admin = require('express')();\nadmin.get('/users/me', h);"),
+        ]);
+        let analysis = check_route_conflicts(&routes, &[], &AnalysisConfig::for_tests());
+
+        assert_ne!(routes[0].root, routes[1].root);
+        assert_eq!(analysis.conflicts.len(), 1);
+        assert_eq!(analysis.conflicts[0].conflict_type, ConflictType::ParameterConflict);
+    }
+
+    fn paths(routes: &[Route]) -> Vec<&str> {
+        routes.iter().map(|route| route.path.as_str()).collect()
+    }
+
+    #[test]
+    fn nested_mounts_join_their_prefixes() {
+        let routes = extract(&[
+            ("app.js", "const app = require('express')();\nconst api = require('./api');\napp.use('/api/', api);"),
+            (
+                "api/index.js",
+                "const router = require('express').Router();\nconst users = require('./users');\n\
+                 router.use('/users', users);\nmodule.exports = router;",
+            ),
+            ("api/users.js", "const router = require('express').Router();\nrouter.get('/:id', h);\nmodule.exports = router;"),
+        ]);
+
+        assert_eq!(paths(&routes), ["/api/users/:id"]);
+        assert_eq!(routes[0].mount_description().as_deref(), Some("/api/ -> /users"));
+    }
+
+    #[test]
+    fn imports_and_requires_resolve_the_same_way() {
+        let routes = extract(&[
+            (
+                "app.js",
+                "import express from 'express';\nimport users from './users.js';\nconst posts = require('./posts');\n\
+                 const app = express();\napp.use('/users', users);\napp.use('/posts', posts);",
+            ),
+            ("users.js", "import { Router } from 'express';\nconst router = Router();\nrouter.get('/', h);\nexport default router;"),
+            ("posts.js", "const router = require('express').Router();\nrouter.get('/', h);\nmodule.exports = router;"),
+        ]);
+
+        assert_eq!(paths(&routes), ["/users", "/posts"]);
+    }
+
+    #[test]
+    fn a_module_without_exports_exports_its_only_router() {
+        let routes = extract(&[
+            ("app.js", "const app = require('express')();\napp.use('/admin', require('./admin'));"),
+            ("admin.js", "const express = require('express');\nconst admin = express.Router();\nadmin.get('/stats', h);"),
+        ]);
+
+        assert_eq!(paths(&routes), ["/admin/stats"]);
+    }
+
+    #[test]
+    fn routers_in_a_mount_cycle_are_still_reported() {
+        let routes = extract(&[
+            (
+                "a.js",
+                "const router = require('express').Router();\nconst b = require('./b');\n\
+                 router.get('/a', h);\nrouter.use('/b', b);\nmodule.exports = router;",
+            ),
+            (
+                "b.js",
+                "const router = require('express').Router();\nconst a = require('./a');\n\
+                 router.get('/b', h);\nrouter.use('/a', a);\nmodule.exports = router;",
+            ),
+        ]);
+
+        assert_eq!(paths(&routes), ["/a", "/b/b"]);
+    }
+
+    fn middleware(route: &Route) -> Vec<(&str, &str)> {
+        route.middleware.iter().map(|m| (m.name.as_str(), m.router.as_str())).collect()
+    }
+
+    #[test]
+    fn use_middleware_only_applies_under_its_path() {
+        let routes = extract(&[(
+            "app.js",
+            "const app = require('express')();\napp.use('/admin', auth);\napp.use('/api/:version', log);\n\
+             app.get('/admin/stats', h);\napp.get('/administrator', h);\napp.get('/api/v1/users', h);",
+        )]);
+
+        assert_eq!(middleware(&routes[0]), [("auth", "app")]);
+        assert!(routes[1].middleware.is_empty());
+        assert_eq!(middleware(&routes[2]), [("log", "app")]);
+    }
+
+    #[test]
+    fn router_middleware_stays_inside_its_router() {
+        let routes = extract(&[
+            (
+                "app.js",
+                "const app = require('express')();\nconst users = require('./users');\napp.use(json);\n\
+                 app.use('/users', cors, users);\napp.get('/health', h);",
+            ),
+            ("users.js", "const router = require('express').Router();\nrouter.use(auth);\nrouter.get('/', h);\nmodule.exports = router;"),
+        ]);
+
+        assert_eq!(paths(&routes), ["/users", "/health"]);
+        assert_eq!(middleware(&routes[0]), [("json", "app"), ("cors", "app"), ("auth", "router")]);
+        assert_eq!(middleware(&routes[1]), [("json", "app")]);
+    }
+
+    #[test]
+    fn inline_middleware_runs_after_use_middleware_in_argument_order() {
+        let routes = extract(&[(
+            "app.js",
+            "const app = require('express')();\napp.use(json);\napp.get('/books', auth, validate(), list);",
+        )]);
+
+        assert_eq!(middleware(&routes[0]), [("json", "app"), ("auth", "app"), ("validate()", "app")]);
+        assert_eq!(routes[0].middleware[1].prefix, None);
+    }
+}
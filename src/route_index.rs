@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+/// A prefix tree over route path segments, used to find the routes that could match the
+/// same URL as a given route without comparing it against every other route.
+///
+/// Candidates are a superset of the routes that really overlap; `RoutePattern` decides.
+#[derive(Default)]
+pub struct RouteIndex {
+    root: Node,
+}
+
+#[derive(Default)]
+struct Node {
+    literals: HashMap<String, Node>,
+    param: Option<Box<Node>>,
+    /// Routes whose path ends at this node
+    ends: Vec<usize>,
+    /// Routes with a segment here the index cannot describe, e.g. `*` or `:id?`, which may
+    /// match any URL that reaches this node
+    open: Vec<usize>,
+}
+
+/// How a path segment is indexed
+enum Segment {
+    /// Plain text, matched case-insensitively like Express does
+    Literal(String),
+    /// A bare `:name` parameter, matching any single non-empty segment
+    Param,
+    /// Anything else: wildcards, optional parts, inline regexes, empty segments
+    Complex,
+}
+
+impl RouteIndex {
+    /// Index the given route paths, identified by their position
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a str>) -> RouteIndex {
+        let mut index = RouteIndex::default();
+        for (id, path) in paths.into_iter().enumerate() {
+            index.insert(id, path);
+        }
+        index
+    }
+
+    fn insert(&mut self, id: usize, path: &str) {
+        let mut node = &mut self.root;
+        for segment in segments(path) {
+            node = match segment {
+                Segment::Literal(text) => node.literals.entry(text).or_default(),
+                Segment::Param => node.param.get_or_insert_with(Box::default),
+                Segment::Complex => {
+                    node.open.push(id);
+                    return;
+                }
+            };
+        }
+        node.ends.push(id);
+    }
+
+    /// Routes that may match some URL `path` also matches, in ascending order
+    pub fn candidates(&self, path: &str) -> Vec<usize> {
+        let segments = segments(path);
+        let mut found = Vec::new();
+        self.root.collect_overlapping(&segments, &mut found);
+
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+impl Node {
+    fn collect_overlapping(&self, segments: &[Segment], found: &mut Vec<usize>) {
+        found.extend(&self.open);
+
+        let Some((segment, rest)) = segments.split_first() else {
+            found.extend(&self.ends);
+            return;
+        };
+
+        match segment {
+            Segment::Literal(text) => {
+                if let Some(child) = self.literals.get(text) {
+                    child.collect_overlapping(rest, found);
+                }
+                if let Some(child) = &self.param {
+                    child.collect_overlapping(rest, found);
+                }
+            }
+            Segment::Param => {
+                for child in self.literals.values().chain(self.param.as_deref()) {
+                    child.collect_overlapping(rest, found);
+                }
+            }
+            Segment::Complex => self.collect_all(found),
+        }
+    }
+
+    /// Every route at or below this node
+    fn collect_all(&self, found: &mut Vec<usize>) {
+        found.extend(&self.open);
+        found.extend(&self.ends);
+        for child in self.literals.values().chain(self.param.as_deref()) {
+            child.collect_all(found);
+        }
+    }
+}
+
+/// Split a route path into indexable segments, stopping after the first complex one.
+///
+/// A single trailing slash is dropped because Express matches paths with or without it.
+fn segments(path: &str) -> Vec<Segment> {
+    let Some(rest) = path.strip_prefix('/') else {
+        return vec![Segment::Complex];
+    };
+    let rest = rest.strip_suffix('/').unwrap_or(rest);
+    if rest.is_empty() {
+        return Vec::new();
+    }
+
+    let mut segments = Vec::new();
+    for text in rest.split('/') {
+        let segment = classify(text);
+        let complex = matches!(segment, Segment::Complex);
+        segments.push(segment);
+        if complex {
+            break;
+        }
+    }
+    segments
+}
+
+fn classify(text: &str) -> Segment {
+    if let Some(name) = text.strip_prefix(':') {
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Segment::Param;
+        }
+        return Segment::Complex;
+    }
+
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~')) {
+        Segment::Literal(text.to_ascii_lowercase())
+    } else {
+        Segment::Complex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route_pattern::{ExpressVersion, RoutePattern};
+
+    const PATHS: [&str; 14] = [
+        "/users",
+        "/users/:id",
+        "/users/me",
+        "/Users/ME/",
+        "/users/:id?",
+        "/users/:id(\\d+)",
+        "/users/*",
+        "*",
+        "/",
+        "/posts/:postId/comments",
+        "/posts/latest/comments",
+        "/ab?cd",
+        "/files/:name.:ext",
+        "/:section/settings",
+    ];
+
+    fn candidates(path: &str) -> Vec<&'static str> {
+        let index = RouteIndex::new(PATHS);
+        index.candidates(path).into_iter().map(|id| PATHS[id]).collect()
+    }
+
+    #[test]
+    fn parameters_and_literals_find_each_other() {
+        assert!(candidates("/users/me").contains(&"/users/:id"));
+        assert!(candidates("/users/:id").contains(&"/users/me"));
+        assert!(candidates("/users/me").contains(&"/Users/ME/"));
+        assert!(candidates("/posts/latest/comments").contains(&"/posts/:postId/comments"));
+        assert!(candidates("/users/settings").contains(&"/:section/settings"));
+        assert!(!candidates("/posts/latest/comments").contains(&"/users/:id"));
+    }
+
+    #[test]
+    fn wildcards_optional_segments_and_regexes_land_on_the_catch_all_branch() {
+        let users = candidates("/users");
+        assert!(users.contains(&"/users/:id?"));
+        assert!(users.contains(&"*"));
+
+        let me = candidates("/users/me");
+        for path in ["/users/:id?", "/users/:id(\\d+)", "/users/*", "*"] {
+            assert!(me.contains(&path), "{} is missing", path);
+        }
+
+        assert!(candidates("/acd").contains(&"/ab?cd"));
+        assert!(candidates("/files/a.txt").contains(&"/files/:name.:ext"));
+        assert_eq!(candidates("*").len(), PATHS.len());
+    }
+
+    #[test]
+    fn every_overlapping_route_is_a_candidate() {
+        let patterns: Vec<RoutePattern> = PATHS
+            .iter()
+            .map(|path| RoutePattern::parse(path, ExpressVersion::Express4).unwrap())
+            .collect();
+        let index = RouteIndex::new(PATHS);
+
+        for (i, path) in PATHS.iter().enumerate() {
+            let candidates = index.candidates(path);
+            for (j, other) in PATHS.iter().enumerate() {
+                if patterns[i].common_url(&patterns[j]).unwrap().is_some() {
+                    assert!(candidates.contains(&j), "{} overlaps {} but is not a candidate", path, other);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::{HashMap, VecDeque};
+use clap::ValueEnum;
+use regex_automata::dfa::{dense, Automaton, StartKind};
+use regex_automata::util::{primitives::StateID, start, syntax};
+use regex_automata::{Anchored, MatchKind};
+use serde::{Serialize, Deserialize};
+
+/// Upper bound on the number of state pairs explored when comparing two patterns
+const SEARCH_LIMIT: usize = 100_000;
+
+/// Bytes tried when searching for a URL, in the order that produces the most readable samples
+const URL_ALPHABET: &[u8] =
+    b"/abcdefghijklmnopqrstuvwxyz0123456789-_.~ABCDEFGHIJKLMNOPQRSTUVWXYZ!$&'()*+,;=:@%";
+
+/// Which path-to-regexp dialect route paths are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize, Default)]
+pub enum ExpressVersion {
+    /// Express 4 (path-to-regexp 0.1): `:id?`, `:id(\\d+)`, `*`, and `?`, `+`, `()` as regex operators
+    #[default]
+    #[value(name = "4")]
+    #[serde(rename = "4")]
+    Express4,
+    /// Express 5 (path-to-regexp 8): `{/:optional}`, `*splat`, no inline regexes
+    #[value(name = "5")]
+    #[serde(rename = "5")]
+    Express5,
+}
+
+/// A route path compiled to the set of concrete URLs Express would dispatch to it
+pub struct RoutePattern {
+    dfa: dense::DFA<Vec<u32>>,
+    start: StateID,
+}
+
+/// A state in each of the two automata being searched together
+type StatePair = (StateID, StateID);
+
+/// How each visited state pair was first reached, for reconstructing the URL
+type Parents = HashMap<StatePair, Option<(StatePair, u8)>>;
+
+/// Outcome of searching the product of two patterns for a URL with some property
+enum Search {
+    Found(String),
+    Exhausted,
+    LimitReached,
+}
+
+impl RoutePattern {
+    /// Compile a route path using the given Express version's matching rules
+    pub fn parse(path: &str, version: ExpressVersion) -> Result<RoutePattern, String> {
+        let regex = match version {
+            ExpressVersion::Express4 => express4_regex(path)?,
+            ExpressVersion::Express5 => express5_regex(path)?,
+        };
+
+        Self::compile(&regex).map_err(|err| format!("Invalid route pattern '{}': {}", path, err))
+    }
+
+    /// A pattern that matches `path` verbatim, with the usual optional trailing slash.
+    /// Used when a path cannot be parsed.
+    pub fn literal(path: &str) -> RoutePattern {
+        Self::compile(&with_optional_trailing_slash(regex::escape(path))).expect("escaped literal is a valid regex")
+    }
+
+    fn compile(regex: &str) -> Result<RoutePattern, String> {
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::DFA::config()
+                    .start_kind(StartKind::Anchored)
+                    .match_kind(MatchKind::All),
+            )
+            // Express matches paths case-insensitively by default
+            .syntax(syntax::Config::new().case_insensitive(true).unicode(false).utf8(false))
+            .build(regex)
+            .map_err(|err| err.to_string())?;
+        let start = dfa
+            .start_state(&start::Config::new().anchored(Anchored::Yes))
+            .map_err(|err| err.to_string())?;
+
+        Ok(RoutePattern { dfa, start })
+    }
+
+    /// The shortest URL matched by both patterns, if they overlap at all.
+    ///
+    /// Fails when the patterns are too complex to decide within the search limit.
+    pub fn common_url(&self, other: &RoutePattern) -> Result<Option<String>, String> {
+        match search(self, other, |a, b| a && b, |dead_a, dead_b| dead_a || dead_b) {
+            Search::Found(url) => Ok(Some(url)),
+            Search::Exhausted => Ok(None),
+            Search::LimitReached => Err(limit_message()),
+        }
+    }
+
+    /// Whether every URL matched by `other` is also matched by this pattern.
+    ///
+    /// Fails when the patterns are too complex to decide within the search limit.
+    pub fn covers(&self, other: &RoutePattern) -> Result<bool, String> {
+        match search(self, other, |a, b| !a && b, |_, dead_b| dead_b) {
+            Search::Found(_) => Ok(false),
+            Search::Exhausted => Ok(true),
+            Search::LimitReached => Err(limit_message()),
+        }
+    }
+
+    fn is_accepting(&self, state: StateID) -> bool {
+        self.dfa.is_match_state(self.dfa.next_eoi_state(state))
+    }
+}
+
+/// Breadth-first search over the product of two patterns' automata for the shortest URL
+/// whose acceptance by each pattern satisfies `accept`
+fn search(
+    a: &RoutePattern,
+    b: &RoutePattern,
+    accept: impl Fn(bool, bool) -> bool,
+    prune: impl Fn(bool, bool) -> bool,
+) -> Search {
+    let mut parents = Parents::new();
+    let mut queue = VecDeque::new();
+
+    let initial = (a.start, b.start);
+    parents.insert(initial, None);
+    queue.push_back(initial);
+
+    while let Some(pair) = queue.pop_front() {
+        if accept(a.is_accepting(pair.0), b.is_accepting(pair.1)) {
+            return Search::Found(reconstruct(&parents, pair));
+        }
+
+        for &byte in URL_ALPHABET {
+            let next = (a.dfa.next_state(pair.0, byte), b.dfa.next_state(pair.1, byte));
+            if prune(a.dfa.is_dead_state(next.0), b.dfa.is_dead_state(next.1)) || parents.contains_key(&next) {
+                continue;
+            }
+
+            if parents.len() >= SEARCH_LIMIT {
+                return Search::LimitReached;
+            }
+            parents.insert(next, Some((pair, byte)));
+            queue.push_back(next);
+        }
+    }
+
+    Search::Exhausted
+}
+
+fn limit_message() -> String {
+    format!("the comparison gave up after {} state pairs", SEARCH_LIMIT)
+}
+
+fn reconstruct(parents: &Parents, mut pair: StatePair) -> String {
+    let mut bytes = Vec::new();
+    while let Some(Some((parent, byte))) = parents.get(&pair) {
+        bytes.push(*byte);
+        pair = *parent;
+    }
+    bytes.reverse();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Translate an Express 4 path into a regex, following path-to-regexp 0.1 semantics
+fn express4_regex(path: &str) -> Result<String, String> {
+    let chars: Vec<char> = path.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            ':' if chars.get(i + 1).is_some_and(|c| is_word_char(*c)) => {
+                i += 1;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+
+                let mut capture = "[^/]+?".to_string();
+                if chars.get(i) == Some(&'(') {
+                    let end = closing_paren(&chars, i)
+                        .ok_or_else(|| format!("Unterminated parameter pattern in '{}'", path))?;
+                    capture = format!("(?:{})", chars[i + 1..end].iter().collect::<String>());
+                    i = end + 1;
+                }
+                if chars.get(i) == Some(&'*') {
+                    capture.push_str("(?:/.*)?");
+                    i += 1;
+                }
+
+                if chars.get(i) == Some(&'?') {
+                    // An optional parameter takes its leading slash with it
+                    if regex.ends_with('/') {
+                        regex.pop();
+                        regex.push_str(&format!("(?:/{})?", capture));
+                    } else {
+                        regex.push_str(&format!("(?:{})?", capture));
+                    }
+                    i += 1;
+                } else {
+                    regex.push_str(&capture);
+                }
+                continue;
+            },
+            '*' => regex.push_str(".*"),
+            '(' => regex.push_str("(?:"),
+            c @ (')' | '?' | '+') => regex.push(c),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    Ok(with_optional_trailing_slash(regex))
+}
+
+/// Translate an Express 5 path into a regex, following path-to-regexp 8 semantics
+fn express5_regex(path: &str) -> Result<String, String> {
+    let chars: Vec<char> = path.chars().collect();
+    let mut regex = String::new();
+    let mut open_groups = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            c @ (':' | '*') => {
+                i += 1;
+                let name_len = parameter_name_len(&chars[i..]);
+                if name_len == 0 {
+                    return Err(format!("Missing parameter name at index {} in '{}'", i, path));
+                }
+                i += name_len;
+                regex.push_str(if c == ':' { "[^/]+" } else { ".+" });
+                continue;
+            },
+            '{' => {
+                open_groups += 1;
+                regex.push_str("(?:");
+            },
+            '}' => {
+                if open_groups == 0 {
+                    return Err(format!("Unexpected '}}' at index {} in '{}'", i, path));
+                }
+                open_groups -= 1;
+                regex.push_str(")?");
+            },
+            '\\' => {
+                i += 1;
+                if let Some(c) = chars.get(i) {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+            },
+            c @ ('(' | ')' | '[' | ']' | '?' | '+' | '!') => {
+                return Err(format!("Unexpected '{}' at index {} in '{}'", c, i, path));
+            },
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    if open_groups > 0 {
+        return Err(format!("Unterminated '{{' in '{}'", path));
+    }
+
+    Ok(with_optional_trailing_slash(regex))
+}
+
+/// Non-strict routing: a single trailing slash is always optional
+fn with_optional_trailing_slash(mut regex: String) -> String {
+    if regex.ends_with('/') {
+        regex.push_str("?$");
+    } else {
+        regex.push_str("/?$");
+    }
+    regex
+}
+
+pub fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Length of an Express 5 parameter name: an identifier or a double-quoted string
+pub fn parameter_name_len(chars: &[char]) -> usize {
+    if chars.first() == Some(&'"') {
+        return chars[1..]
+            .iter()
+            .position(|c| *c == '"')
+            .map(|end| end + 2)
+            .unwrap_or(0);
+    }
+
+    chars
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '$')
+        .count()
+}
+
+/// Index of the parenthesis closing the one at `open`
+pub fn closing_paren(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(path: &str, version: ExpressVersion) -> RoutePattern {
+        RoutePattern::parse(path, version).unwrap()
+    }
+
+    fn matches(pattern: &RoutePattern, url: &str) -> bool {
+        let state = url.bytes().fold(pattern.start, |state, byte| pattern.dfa.next_state(state, byte));
+        pattern.is_accepting(state)
+    }
+
+    #[test]
+    fn express4_optional_parameters_take_their_slash() {
+        let users = pattern("/users/:id?", ExpressVersion::Express4);
+
+        assert!(matches(&users, "/users"));
+        assert!(matches(&users, "/users/"));
+        assert!(matches(&users, "/users/42"));
+        assert!(!matches(&users, "/users/42/posts"));
+    }
+
+    #[test]
+    fn express4_wildcards_and_custom_parameters() {
+        let files = pattern("/files/*", ExpressVersion::Express4);
+        assert!(matches(&files, "/files/a/b/c"));
+        assert!(!matches(&files, "/file"));
+
+        let numeric = pattern("/users/:id(\\d+)", ExpressVersion::Express4);
+        assert!(matches(&numeric, "/users/42"));
+        assert!(!matches(&numeric, "/users/me"));
+    }
+
+    #[test]
+    fn express5_groups_and_named_wildcards() {
+        let docs = pattern("/docs{/:section}", ExpressVersion::Express5);
+        assert!(matches(&docs, "/docs"));
+        assert!(matches(&docs, "/docs/intro"));
+        assert!(!matches(&docs, "/docs/intro/more"));
+
+        let files = pattern("/files/*path", ExpressVersion::Express5);
+        assert!(matches(&files, "/files/a/b"));
+        assert!(!matches(&files, "/files/"));
+
+        assert!(RoutePattern::parse("/users/:id(\\d+)", ExpressVersion::Express5).is_err());
+        assert!(RoutePattern::parse("/docs{/:section", ExpressVersion::Express5).is_err());
+    }
+
+    #[test]
+    fn one_trailing_slash_is_optional_and_case_is_ignored() {
+        let users = pattern("/users", ExpressVersion::Express4);
+
+        assert!(matches(&users, "/users"));
+        assert!(matches(&users, "/users/"));
+        assert!(matches(&users, "/USERS"));
+        assert!(!matches(&users, "/users//"));
+    }
+
+    #[test]
+    fn covers_is_stricter_than_common_url() {
+        let by_id = pattern("/users/:id", ExpressVersion::Express4);
+        let me = pattern("/users/me", ExpressVersion::Express4);
+        assert_eq!(by_id.common_url(&me), Ok(Some("/users/me".to_string())));
+        assert_eq!(by_id.covers(&me), Ok(true));
+        assert_eq!(me.covers(&by_id), Ok(false));
+
+        let numeric = pattern("/users/:id(\\d+)", ExpressVersion::Express4);
+        let by_name = pattern("/users/:name", ExpressVersion::Express4);
+        assert!(numeric.common_url(&by_name).unwrap().is_some());
+        assert_eq!(by_name.covers(&numeric), Ok(true));
+        assert_eq!(numeric.covers(&by_name), Ok(false));
+
+        let posts = pattern("/posts/:id", ExpressVersion::Express4);
+        assert_eq!(by_id.common_url(&posts), Ok(None));
+    }
+
+    #[test]
+    fn literal_fallback_ignores_a_trailing_slash() {
+        assert!(RoutePattern::parse("/a/:b(", ExpressVersion::Express4).is_err());
+
+        let unparseable = RoutePattern::literal("/a/:b(");
+        assert!(matches(&unparseable, "/a/:b("));
+        assert!(matches(&unparseable, "/a/:b(/"));
+        assert_eq!(unparseable.covers(&RoutePattern::literal("/a/:b(/")), Ok(true));
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Serialize, Deserialize};
+use crate::js_parser::{self, ModuleScan};
+use crate::utils::fnv1a;
+
+/// Name of the cache file kept in the project directory when there is no user cache directory
+pub const CACHE_FILE_NAME: &str = ".insidious-cache.json";
+
+/// Version of the cache file format
+const CACHE_VERSION: u32 = 3;
+
+/// Parse results of previous runs, so that only files that changed are parsed again
+#[derive(Serialize, Deserialize)]
+pub struct ScanCache {
+    version: u32,
+    /// Version of insidious that wrote the cache; another version may parse files differently
+    insidious_version: String,
+    /// Entries keyed by file path relative to the project directory
+    files: HashMap<String, CacheEntry>,
+}
+
+/// A file's parse result along with what identified the file's content when it was parsed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch
+    modified: u64,
+    /// FNV-1a hash of the content, which catches files whose modification time changed
+    /// without their content changing, e.g. after a branch switch
+    hash: String,
+    scan: ModuleScan,
+}
+
+impl Default for ScanCache {
+    fn default() -> Self {
+        ScanCache {
+            version: CACHE_VERSION,
+            insidious_version: env!("CARGO_PKG_VERSION").to_string(),
+            files: HashMap::new(),
+        }
+    }
+}
+
+impl ScanCache {
+    /// Location of the cache for a project: a file named after a hash of the project's
+    /// absolute path in the user's cache directory, or a file in the project directory
+    /// when there is no cache directory
+    pub fn path(project_directory: &str) -> PathBuf {
+        ScanCache::path_in(project_directory, user_cache_directory(&|name| env::var_os(name)))
+    }
+
+    fn path_in(project_directory: &str, cache_directory: Option<PathBuf>) -> PathBuf {
+        let Some(cache_directory) = cache_directory else {
+            return Path::new(project_directory).join(CACHE_FILE_NAME);
+        };
+
+        let project = fs::canonicalize(project_directory).unwrap_or_else(|_| PathBuf::from(project_directory));
+        let key = fnv1a(project.to_string_lossy().as_bytes());
+        cache_directory.join("insidious").join(format!("{:016x}.json", key))
+    }
+
+    /// Load a cache, starting empty when it is missing, unreadable or from another version
+    pub fn load(path: &Path) -> ScanCache {
+        let cache: Option<ScanCache> = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+
+        let current = ScanCache::default();
+        match cache {
+            Some(cache) if cache.version == current.version && cache.insidious_version == current.insidious_version => cache,
+            _ => current,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|err| format!("Could not serialize the cache: {}", err))?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)
+                .map_err(|err| format!("Could not create cache directory '{}': {}", directory.display(), err))?;
+        }
+        fs::write(path, json)
+            .map_err(|err| format!("Could not write cache file '{}': {}", path.display(), err))
+    }
+
+    /// Scan a module, reusing the cached result when the file is unchanged.
+    ///
+    /// Also returns the entry to record for the file when the cached one is missing or outdated.
+    pub fn scan(&self, key: &str, file_path: &str, content: &str) -> (ModuleScan, Option<CacheEntry>) {
+        let (size, modified) = file_stamp(file_path);
+        let cached = self.files.get(key);
+
+        if let Some(entry) = cached {
+            if entry.size == size && entry.modified == modified {
+                return (entry.scan.clone(), None);
+            }
+        }
+
+        let hash = format!("{:016x}", fnv1a(content.as_bytes()));
+        let scan = match cached {
+            Some(entry) if entry.hash == hash => entry.scan.clone(),
+            _ => js_parser::scan_module(file_path, content),
+        };
+
+        let entry = CacheEntry { size, modified, hash, scan: scan.clone() };
+        (scan, Some(entry))
+    }
+
+    /// Record new entries and forget files that no longer exist. Returns whether anything changed.
+    pub fn update(&mut self, keys: &HashSet<String>, entries: Vec<(String, CacheEntry)>) -> bool {
+        let before = self.files.len();
+        self.files.retain(|key, _| keys.contains(key));
+        let changed = self.files.len() != before || !entries.is_empty();
+
+        self.files.extend(entries);
+        changed
+    }
+}
+
+/// `$XDG_CACHE_HOME`, `~/.cache` or `%LOCALAPPDATA%`, whichever is set first in the
+/// environment variables looked up with `env_var`
+fn user_cache_directory(env_var: &dyn Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let from_env = |name: &str| env_var(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+
+    from_env("XDG_CACHE_HOME")
+        .or_else(|| from_env("HOME").map(|home| home.join(".cache")))
+        .or_else(|| from_env("LOCALAPPDATA"))
+}
+
+/// Remove a project's cache. Returns whether there was one.
+pub fn clear(project_directory: &str) -> Result<bool, String> {
+    let path = ScanCache::path(project_directory);
+    if !path.is_file() {
+        return Ok(false);
+    }
+
+    fs::remove_file(&path)
+        .map(|_| true)
+        .map_err(|err| format!("Error: Could not remove cache file '{}': {}", path.display(), err))
+}
+
+/// A file's size and modification time, or zeroes when they cannot be read
+fn file_stamp(file_path: &str) -> (u64, u64) {
+    let Ok(metadata) = fs::metadata(file_path) else {
+        return (0, 0);
+    };
+
+    let modified = metadata.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0);
+
+    (metadata.len(), modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    const SOURCE: &str = "const app = express();\napp.get('/users', list);\n";
+
+    /// A fresh temporary directory for one test
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("insidious-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Write `content` to `path` and give it a fixed modification time
+    fn write_file(path: &Path, content: &str, modified_secs: u64) {
+        fs::write(path, content).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs)).unwrap();
+    }
+
+    /// A cache holding the scan of `file`, recorded under the key "app.js"
+    fn cache_with(file: &Path, content: &str) -> ScanCache {
+        let mut cache = ScanCache::default();
+        let (_, entry) = cache.scan("app.js", file.to_str().unwrap(), content);
+        let keys = HashSet::from(["app.js".to_string()]);
+        assert!(cache.update(&keys, vec![("app.js".to_string(), entry.unwrap())]));
+        cache
+    }
+
+    fn route_paths(scan: &ModuleScan) -> Vec<String> {
+        scan.routes.iter().map(|route| route.path.clone()).collect()
+    }
+
+    #[test]
+    fn unchanged_size_and_modification_time_reuse_the_cached_scan() {
+        let file = temp_directory("hit").join("app.js");
+        write_file(&file, SOURCE, 1_000);
+        let cache = cache_with(&file, SOURCE);
+
+        // The content passed in is ignored: the stamp alone says the file is unchanged
+        let (scan, entry) = cache.scan("app.js", file.to_str().unwrap(), "");
+        assert_eq!(route_paths(&scan), ["/users"]);
+        assert!(entry.is_none());
+    }
+
+    #[test]
+    fn a_new_stamp_with_the_same_content_reuses_the_scan_but_records_the_stamp() {
+        let file = temp_directory("touch").join("app.js");
+        write_file(&file, SOURCE, 1_000);
+        let cache = cache_with(&file, SOURCE);
+
+        write_file(&file, SOURCE, 2_000);
+        let (scan, entry) = cache.scan("app.js", file.to_str().unwrap(), SOURCE);
+        assert_eq!(route_paths(&scan), ["/users"]);
+        let entry = entry.unwrap();
+        assert_eq!(entry.modified, 2_000_000_000_000);
+        assert_eq!(entry.hash, cache.files["app.js"].hash);
+    }
+
+    #[test]
+    fn changed_content_is_parsed_again() {
+        let file = temp_directory("miss").join("app.js");
+        write_file(&file, SOURCE, 1_000);
+        let cache = cache_with(&file, SOURCE);
+
+        let changed = SOURCE.replace("/users", "/people");
+        write_file(&file, &changed, 1_000);
+        let (scan, entry) = cache.scan("app.js", file.to_str().unwrap(), &changed);
+        assert_eq!(route_paths(&scan), ["/people"]);
+        assert_ne!(entry.unwrap().hash, cache.files["app.js"].hash);
+    }
+
+    #[test]
+    fn caches_from_other_versions_are_discarded() {
+        let directory = temp_directory("version");
+        let file = directory.join("app.js");
+        write_file(&file, SOURCE, 1_000);
+        let cache_path = directory.join("cache.json");
+
+        let cache = cache_with(&file, SOURCE);
+        cache.save(&cache_path).unwrap();
+        assert_eq!(ScanCache::load(&cache_path).files.len(), 1);
+
+        let old_format = ScanCache { version: CACHE_VERSION - 1, ..cache_with(&file, SOURCE) };
+        old_format.save(&cache_path).unwrap();
+        assert!(ScanCache::load(&cache_path).files.is_empty());
+
+        let other_release = ScanCache { insidious_version: "0.0.0-other".to_string(), ..cache_with(&file, SOURCE) };
+        other_release.save(&cache_path).unwrap();
+        assert!(ScanCache::load(&cache_path).files.is_empty());
+
+        fs::write(&cache_path, "not json").unwrap();
+        assert!(ScanCache::load(&cache_path).files.is_empty());
+    }
+
+    #[test]
+    fn the_cache_falls_back_to_the_project_directory() {
+        let project = temp_directory("fallback");
+        let project = project.to_str().unwrap();
+
+        assert_eq!(ScanCache::path_in(project, None), Path::new(project).join(CACHE_FILE_NAME));
+
+        let in_cache_directory = ScanCache::path_in(project, Some(PathBuf::from("/cache")));
+        assert!(in_cache_directory.starts_with("/cache/insidious"));
+        assert_eq!(in_cache_directory, ScanCache::path_in(&format!("{}/", project), Some(PathBuf::from("/cache"))));
+    }
+
+    #[test]
+    fn the_first_set_cache_directory_variable_wins() {
+        let environment = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| OsString::from(value))
+        };
+
+        let all = environment(&[("XDG_CACHE_HOME", "/xdg"), ("HOME", "/home/me"), ("LOCALAPPDATA", "/local")]);
+        assert_eq!(user_cache_directory(&all), Some(PathBuf::from("/xdg")));
+        let empty_xdg = environment(&[("XDG_CACHE_HOME", ""), ("HOME", "/home/me")]);
+        assert_eq!(user_cache_directory(&empty_xdg), Some(PathBuf::from("/home/me/.cache")));
+        let windows = environment(&[("LOCALAPPDATA", "/local")]);
+        assert_eq!(user_cache_directory(&windows), Some(PathBuf::from("/local")));
+        assert_eq!(user_cache_directory(&environment(&[])), None);
+    }
+}
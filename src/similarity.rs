@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+use clap::ValueEnum;
+use serde::{Serialize, Deserialize};
+use similar::TextDiff;
+
+/// Placeholder every route parameter is normalized to before comparing paths
+const PARAM_PLACEHOLDER: &str = ":param";
+
+/// How similar two route paths are, as a percentage between 0 and 100
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SimilarityAlgorithm {
+    /// Edit distance over path segments, where near-identical segments cost less to substitute
+    #[default]
+    SegmentLevenshtein,
+    /// Shared segments divided by all distinct segments
+    Jaccard,
+    /// Character-level diff ratio of the normalized paths
+    CharDiff,
+}
+
+/// Score the similarity of two route paths with the given algorithm, bounded to 0–100
+pub fn path_similarity(path1: &str, path2: &str, algorithm: SimilarityAlgorithm) -> f64 {
+    let segments1 = normalized_segments(path1);
+    let segments2 = normalized_segments(path2);
+
+    if segments1 == segments2 {
+        return 100.0;
+    }
+
+    let score = match algorithm {
+        SimilarityAlgorithm::SegmentLevenshtein => segment_levenshtein(&segments1, &segments2),
+        SimilarityAlgorithm::Jaccard => jaccard(&segments1, &segments2),
+        SimilarityAlgorithm::CharDiff => char_diff(&segments1, &segments2),
+    };
+
+    (score * 100.0).clamp(0.0, 100.0)
+}
+
+/// Split a path into its non-empty segments, with every parameter replaced by a placeholder
+fn normalized_segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            if segment.starts_with(':') || segment.starts_with('{') || (segment.starts_with('*') && segment.len() > 1) {
+                PARAM_PLACEHOLDER.to_string()
+            } else {
+                segment.to_lowercase()
+            }
+        })
+        .collect()
+}
+
+fn segment_levenshtein(segments1: &[String], segments2: &[String]) -> f64 {
+    let longest = segments1.len().max(segments2.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    // Classic edit distance, except that substituting one segment for another only
+    // costs as much as the two segments differ
+    let mut previous: Vec<f64> = (0..=segments2.len()).map(|j| j as f64).collect();
+    for (i, segment1) in segments1.iter().enumerate() {
+        let mut current = vec![(i + 1) as f64];
+        for (j, segment2) in segments2.iter().enumerate() {
+            let substitution = previous[j] + (1.0 - segment_similarity(segment1, segment2));
+            let deletion = previous[j + 1] + 1.0;
+            let insertion = current[j] + 1.0;
+            current.push(substitution.min(deletion).min(insertion));
+        }
+        previous = current;
+    }
+
+    1.0 - previous[segments2.len()] / longest as f64
+}
+
+/// Normalized character edit-distance similarity of two segments, between 0 and 1
+fn segment_similarity(segment1: &str, segment2: &str) -> f64 {
+    if segment1 == segment2 {
+        return 1.0;
+    }
+    // A parameter could hold the other segment's value, but that is no more than a possibility
+    if segment1 == PARAM_PLACEHOLDER || segment2 == PARAM_PLACEHOLDER {
+        return 0.5;
+    }
+
+    let chars1: Vec<char> = segment1.chars().collect();
+    let chars2: Vec<char> = segment2.chars().collect();
+
+    let mut previous: Vec<usize> = (0..=chars2.len()).collect();
+    for (i, c1) in chars1.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, c2) in chars2.iter().enumerate() {
+            let substitution = previous[j] + usize::from(c1 != c2);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    let longest = chars1.len().max(chars2.len());
+    1.0 - previous[chars2.len()] as f64 / longest as f64
+}
+
+fn jaccard(segments1: &[String], segments2: &[String]) -> f64 {
+    let set1: HashSet<&String> = segments1.iter().collect();
+    let set2: HashSet<&String> = segments2.iter().collect();
+
+    let union = set1.union(&set2).count();
+    if union == 0 {
+        return 1.0;
+    }
+
+    set1.intersection(&set2).count() as f64 / union as f64
+}
+
+fn char_diff(segments1: &[String], segments2: &[String]) -> f64 {
+    let path1 = format!("/{}", segments1.join("/"));
+    let path2 = format!("/{}", segments2.join("/"));
+
+    f64::from(TextDiff::from_chars(&path1, &path2).ratio())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [SimilarityAlgorithm; 3] = [
+        SimilarityAlgorithm::SegmentLevenshtein,
+        SimilarityAlgorithm::Jaccard,
+        SimilarityAlgorithm::CharDiff,
+    ];
+
+    #[test]
+    fn identical_paths_and_parameter_spellings_score_100() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(path_similarity("/users/:id", "/users/:id", algorithm), 100.0);
+            assert_eq!(path_similarity("/users/:id", "/users/{id}", algorithm), 100.0);
+            assert_eq!(path_similarity("/Users/", "/users", algorithm), 100.0);
+            assert_eq!(path_similarity("", "/", algorithm), 100.0);
+        }
+    }
+
+    #[test]
+    fn disjoint_paths_score_low() {
+        assert_eq!(path_similarity("/abc/def", "/xyz/uvw", SimilarityAlgorithm::SegmentLevenshtein), 0.0);
+        assert_eq!(path_similarity("/abc/def", "/xyz/uvw", SimilarityAlgorithm::Jaccard), 0.0);
+        assert!(path_similarity("/abc/def", "/xyz/uvw", SimilarityAlgorithm::CharDiff) < 50.0);
+
+        assert_eq!(path_similarity("", "/users", SimilarityAlgorithm::SegmentLevenshtein), 0.0);
+        assert_eq!(path_similarity("", "/users", SimilarityAlgorithm::Jaccard), 0.0);
+        assert!(path_similarity("", "/users", SimilarityAlgorithm::CharDiff) < 50.0);
+    }
+
+    #[test]
+    fn segments_that_share_a_suffix_are_partly_similar() {
+        let score = |algorithm| path_similarity("/stripe-webhook", "/hubtel-webhook", algorithm);
+
+        assert!((50.0..100.0).contains(&score(SimilarityAlgorithm::SegmentLevenshtein)));
+        assert!((50.0..100.0).contains(&score(SimilarityAlgorithm::CharDiff)));
+        // Jaccard only counts whole segments
+        assert_eq!(score(SimilarityAlgorithm::Jaccard), 0.0);
+    }
+
+    #[test]
+    fn scores_stay_between_0_and_100() {
+        let paths = [
+            "",
+            "/",
+            "/users",
+            "/users/:id",
+            "/users/{id}/posts",
+            "/stripe-webhook",
+            "/hubtel-webhook",
+            "/a/b/c/d/e/f",
+            "*",
+            "/files/*path",
+        ];
+
+        for algorithm in ALGORITHMS {
+            for path1 in paths {
+                for path2 in paths {
+                    let score = path_similarity(path1, path2, algorithm);
+                    assert!((0.0..=100.0).contains(&score), "{:?} scored {} for {} and {}", algorithm, score, path1, path2);
+                    assert_eq!(score, path_similarity(path2, path1, algorithm));
+                }
+            }
+        }
+    }
+}
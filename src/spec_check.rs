@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use crate::cli::AnalysisConfig;
+use crate::models::Route;
+use crate::openapi::path_templates;
+use crate::output::{route_line, Section, SectionedOutput};
+use crate::utils::join_route_paths;
+
+/// Exit code of `insidious spec-check` when the routes and the spec disagree
+pub const DRIFT_EXIT_CODE: i32 = 5;
+
+/// Path item keys that hold operations, in OpenAPI 2 and 3
+const SPEC_METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// The operations documented in an OpenAPI 2 or 3 document
+pub struct Spec {
+    /// e.g. `OpenAPI 3.0.3` or `Swagger 2.0`
+    pub version: String,
+    pub operations: Vec<SpecOperation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecOperation {
+    /// Upper case, like route methods
+    pub method: String,
+    /// The path as written in the spec, with the base path in front
+    pub path: String,
+    /// The same path with Express parameters, e.g. `/users/:id` for `/users/{id}`
+    pub express_path: String,
+    /// Names of the path parameters in the order they appear
+    pub parameters: Vec<String>,
+}
+
+/// Where the routes of a project and an OpenAPI spec disagree
+#[derive(Serialize)]
+pub struct SpecDrift {
+    pub spec: String,
+    pub spec_version: String,
+    /// Routes without a documented operation
+    pub undocumented: Vec<Route>,
+    /// Documented operations without a route
+    pub unimplemented: Vec<SpecOperation>,
+    pub parameter_mismatches: Vec<ParameterMismatch>,
+}
+
+/// A route and a documented operation on the same path whose parameters are named differently
+#[derive(Serialize)]
+pub struct ParameterMismatch {
+    pub route: Route,
+    pub operation: SpecOperation,
+    pub route_parameters: Vec<String>,
+}
+
+impl Spec {
+    /// Load a JSON or YAML spec. Paths are prefixed with `base_path` when given, and
+    /// otherwise with the spec's `basePath` (OpenAPI 2) or the path of its first server (OpenAPI 3).
+    pub fn load(path: &Path, base_path: Option<&str>) -> Result<Spec, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Error: Could not read spec '{}': {}", path.display(), err))?;
+
+        let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let document: Value = if is_json {
+            serde_json::from_str(&content).map_err(|err| err.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|err| err.to_string())
+        }
+        .map_err(|err| format!("Error: Invalid spec '{}': {}", path.display(), err))?;
+
+        let version = match (document.get("swagger"), document.get("openapi")) {
+            (Some(Value::String(version)), _) if version.starts_with('2') => format!("Swagger {}", version),
+            (_, Some(Value::String(version))) if version.starts_with('3') => format!("OpenAPI {}", version),
+            _ => {
+                return Err(format!(
+                    "Error: '{}' is not an OpenAPI 2 or 3 document: it has no 'swagger: \"2.0\"' or 'openapi: 3.x' field",
+                    path.display()
+                ));
+            }
+        };
+
+        let base_path = match base_path {
+            Some(base_path) => base_path.to_string(),
+            None => document_base_path(&document),
+        };
+
+        let mut operations = Vec::new();
+        if let Some(Value::Object(paths)) = document.get("paths") {
+            for (template, item) in paths.iter().filter(|(template, _)| template.starts_with('/')) {
+                let full_path = join_route_paths(&base_path, template);
+                for method in SPEC_METHODS.iter().filter(|method| item.get(**method).is_some()) {
+                    operations.push(SpecOperation {
+                        method: method.to_uppercase(),
+                        path: full_path.clone(),
+                        express_path: express_path(&full_path),
+                        parameters: template_parameters(&full_path),
+                    });
+                }
+            }
+        }
+
+        Ok(Spec { version, operations })
+    }
+}
+
+impl SpecDrift {
+    /// Match routes and documented operations by method and path shape: literal segments
+    /// compared case-insensitively, parameters compared by position only.
+    ///
+    /// A route with optional parts is documented when any of its forms is. An `app.all()`
+    /// route implements every documented method on its path, and a `GET` route also implements
+    /// a documented `HEAD`, which Express answers with it.
+    pub fn new(spec_name: &str, spec: &Spec, routes: &[Route], config: &AnalysisConfig) -> SpecDrift {
+        let mut documented: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        for (index, operation) in spec.operations.iter().enumerate() {
+            documented.entry((operation.method.clone(), shape(&operation.path))).or_default().push(index);
+        }
+
+        let mut implemented = vec![false; spec.operations.len()];
+        let mut undocumented = Vec::new();
+        let mut parameter_mismatches = Vec::new();
+
+        let mut routes: Vec<&Route> = routes.iter().collect();
+        routes.sort_by_key(|route| route.order);
+
+        for route in routes {
+            let mut is_documented = false;
+
+            for (template, parameters) in path_templates(&route.path, config.express_version) {
+                let route_shape = shape(&template);
+                let implied_head = match route.method.as_str() {
+                    "GET" => documented.get(&("HEAD".to_string(), route_shape.clone())).cloned().unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                let matches: Vec<usize> = if route.method == "ALL" {
+                    SPEC_METHODS.iter()
+                        .filter_map(|method| documented.get(&(method.to_uppercase(), route_shape.clone())))
+                        .flatten()
+                        .copied()
+                        .collect()
+                } else {
+                    documented.get(&(route.method.clone(), route_shape)).cloned().unwrap_or_default()
+                };
+
+                is_documented |= !matches.is_empty();
+
+                let route_parameters: Vec<String> = parameters.into_iter().map(|parameter| parameter.name).collect();
+                for index in matches.into_iter().chain(implied_head) {
+                    if !implemented[index] && spec.operations[index].parameters != route_parameters {
+                        parameter_mismatches.push(ParameterMismatch {
+                            route: route.clone(),
+                            operation: spec.operations[index].clone(),
+                            route_parameters: route_parameters.clone(),
+                        });
+                    }
+                    implemented[index] = true;
+                }
+            }
+
+            if !is_documented {
+                undocumented.push(route.clone());
+            }
+        }
+
+        let unimplemented = spec.operations
+            .iter()
+            .zip(implemented)
+            .filter(|(_, implemented)| !implemented)
+            .map(|(operation, _)| operation.clone())
+            .collect();
+
+        SpecDrift {
+            spec: spec_name.to_string(),
+            spec_version: spec.version.clone(),
+            undocumented,
+            unimplemented,
+            parameter_mismatches,
+        }
+    }
+
+    pub fn has_drift(&self) -> bool {
+        !self.undocumented.is_empty() || !self.unimplemented.is_empty() || !self.parameter_mismatches.is_empty()
+    }
+}
+
+impl SectionedOutput for SpecDrift {
+    fn heading(&self, markdown: bool) -> String {
+        let quote = if markdown { "`" } else { "" };
+        format!("Routes compared with {q}{}{q} ({})", self.spec, self.spec_version, q = quote)
+    }
+
+    fn summary_table(&self) -> String {
+        let mut md = String::new();
+        md.push_str("| Undocumented | Not Implemented | Parameter Mismatches |\n");
+        md.push_str("|--------------|-----------------|----------------------|\n");
+        md.push_str(&format!(
+            "| {} | {} | {} |\n",
+            self.undocumented.len(), self.unimplemented.len(), self.parameter_mismatches.len()
+        ));
+        md
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        vec![
+            Section {
+                title: "Implemented but Undocumented",
+                marker: '+',
+                lines: self.undocumented.iter().map(route_line).collect(),
+            },
+            Section {
+                title: "Documented but Not Implemented",
+                marker: '-',
+                lines: self.unimplemented.iter()
+                    .map(|operation| format!("{} {} ({})", operation.method, operation.path, operation.express_path))
+                    .collect(),
+            },
+            Section {
+                title: "Parameter Mismatches",
+                marker: '~',
+                lines: self.parameter_mismatches.iter()
+                    .map(|mismatch| format!(
+                        "{} {}: code names {}, spec names {} ({}:{})",
+                        mismatch.route.method,
+                        mismatch.route.path,
+                        parameter_list(&mismatch.route_parameters, ":", ""),
+                        parameter_list(&mismatch.operation.parameters, "{", "}"),
+                        mismatch.route.file_path,
+                        mismatch.route.line_number
+                    ))
+                    .collect(),
+            },
+        ]
+    }
+}
+
+/// The path prefix every operation of the document is served under
+fn document_base_path(document: &Value) -> String {
+    if let Some(base_path) = document.get("basePath").and_then(Value::as_str) {
+        return base_path.to_string();
+    }
+
+    let Some(server) = document.get("servers").and_then(|servers| servers.get(0)) else {
+        return String::new();
+    };
+    let mut url = server.get("url").and_then(Value::as_str).unwrap_or_default().to_string();
+
+    // Fill in server variables such as `{version}` with their defaults
+    if let Some(Value::Object(variables)) = server.get("variables") {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+    }
+
+    // Keep only the path of absolute URLs such as `https://api.example.com/v1`
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|start| rest[start..].to_string()).unwrap_or_default(),
+        None => url,
+    }
+}
+
+/// Convert `{param}` path parameters to Express `:param` parameters
+fn express_path(template: &str) -> String {
+    let mut path = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        path.push_str(&rest[..start]);
+        path.push(':');
+        path.push_str(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+
+    path.push_str(rest);
+    path
+}
+
+/// Names of the `{param}` path parameters of a template
+fn template_parameters(template: &str) -> Vec<String> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+/// A template with parameter names left out, lower case and without a trailing slash,
+/// so that paths Express would dispatch the same way compare equal
+fn shape(template: &str) -> String {
+    let mut shape = String::new();
+    let mut in_parameter = false;
+
+    for c in template.chars() {
+        match c {
+            '{' => {
+                in_parameter = true;
+                shape.push_str("{}");
+            },
+            '}' => in_parameter = false,
+            c if !in_parameter => shape.extend(c.to_lowercase()),
+            _ => {},
+        }
+    }
+
+    if shape.len() > 1 && shape.ends_with('/') {
+        shape.pop();
+    }
+    shape
+}
+
+fn parameter_list(names: &[String], open: &str, close: &str) -> String {
+    if names.is_empty() {
+        return "no parameters".to_string();
+    }
+    names.iter().map(|name| format!("{}{}{}", open, name, close)).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(method: &str, path: &str) -> SpecOperation {
+        SpecOperation {
+            method: method.to_string(),
+            path: path.to_string(),
+            express_path: express_path(path),
+            parameters: template_parameters(path),
+        }
+    }
+
+    #[test]
+    fn get_routes_implement_documented_head_operations() {
+        let spec = Spec {
+            version: "OpenAPI 3.0.3".to_string(),
+            operations: vec![operation("GET", "/users"), operation("HEAD", "/users"), operation("HEAD", "/ping")],
+        };
+        let routes = [Route::for_tests("GET", "/users", 0), Route::for_tests("GET", "/ping", 1)];
+        let drift = SpecDrift::new("openapi.yaml", &spec, &routes, &AnalysisConfig::for_tests());
+
+        assert!(drift.unimplemented.is_empty());
+        assert_eq!(drift.undocumented.iter().map(|route| route.path.as_str()).collect::<Vec<_>>(), ["/ping"]);
+    }
+}
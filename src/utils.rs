@@ -1,17 +1,56 @@
-use similar::{ChangeTag, TextDiff};
+use std::path::{Component, Path, PathBuf};
+/// Join a mount prefix and a route path the way Express does when a router is mounted
+pub fn join_route_paths(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    let path = path.trim_start_matches('/');
 
-pub fn calculate_path_similarity(path1: &str, path2: &str) -> f64 {
-    let diff = TextDiff::from_chars(path1, path2);
-    let matching_chars: usize = diff
-        .iter_all_changes()
-        .filter(|change| change.tag() == ChangeTag::Equal)
-        .map(|change| change.to_string().len())
-        .sum();
-    
-    let max_len = path1.len().max(path2.len());
-    if max_len == 0 {
-        return 0.0;
+    match (prefix.is_empty(), path.is_empty()) {
+        (true, true) => "/".to_string(),
+        (true, false) => format!("/{}", path),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}/{}", prefix, path),
     }
-    
-    (matching_chars as f64 / max_len as f64) * 100.0
-}
\ No newline at end of file
+}
+
+/// Lexically normalize a path, resolving `.` and `..` components without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+
+    normalized
+}
+
+/// 64-bit FNV-1a, used because its output never changes between Rust releases
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_paths_join_with_a_single_slash() {
+        assert_eq!(join_route_paths("/", "/"), "/");
+        assert_eq!(join_route_paths("", "users"), "/users");
+        assert_eq!(join_route_paths("/api/", "/users"), "/api/users");
+        assert_eq!(join_route_paths("/api", "users/"), "/api/users/");
+        assert_eq!(join_route_paths("/api", "/"), "/api");
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use crate::baseline::conflict_changes;
+use crate::cli::AnalysisConfig;
+use crate::models::AnalysisReport;
+use crate::output::conflict_line;
+use crate::route_extractor::{Extraction, ProjectScan};
+
+/// How long to wait for further events before re-analyzing, so that a burst of writes
+/// (a save, a branch switch) is handled once
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Analyze a project, then re-analyze it whenever its source files change and print the
+/// conflicts each change introduced or resolved. Runs until the process is stopped.
+pub fn watch(
+    config: &AnalysisConfig,
+    analyze: impl Fn(Extraction, &AnalysisConfig) -> Result<AnalysisReport, String>,
+) -> Result<(), String> {
+    let root = fs::canonicalize(&config.project_directory)
+        .map_err(|err| format!("Error: Could not watch '{}': {}", config.project_directory, err))?;
+
+    let mut project = ProjectScan::new(config);
+    let mut conflicts = analyze(project.extraction(), config)?.conflicts;
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)
+        .map_err(|err| format!("Error: Could not start watching for changes: {}", err))?;
+    watcher.watch(&root, RecursiveMode::Recursive)
+        .map_err(|err| format!("Error: Could not watch '{}': {}", config.project_directory, err))?;
+
+    println!("Watching {} for changes. Press Ctrl+C to stop.", config.project_directory);
+    println!("Current conflicts: {}", conflicts.len());
+    for conflict in &conflicts {
+        println!("  {}", conflict_line(conflict));
+    }
+
+    while let Ok(event) = receiver.recv() {
+        let mut changed = Vec::new();
+        collect_paths(event, &root, config, &mut changed);
+        while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+            collect_paths(event, &root, config, &mut changed);
+        }
+        changed.sort();
+        changed.dedup();
+
+        if changed.is_empty() || !project.update(&changed, config) {
+            continue;
+        }
+
+        let report = match analyze(project.extraction(), config) {
+            Ok(report) => report,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+
+        let (introduced, resolved) = conflict_changes(&conflicts, &report.conflicts, &config.project_directory);
+        println!("\nRe-analyzed: {} routes, {} conflicts", report.total_routes, report.conflict_count);
+        if introduced.is_empty() && resolved.is_empty() {
+            println!("  No conflicts introduced or resolved");
+        }
+        for conflict in introduced {
+            println!("  + {}", conflict_line(conflict));
+        }
+        for conflict in resolved {
+            println!("  - {}", conflict_line(conflict));
+        }
+
+        conflicts = report.conflicts;
+    }
+
+    Ok(())
+}
+
+/// Add the project paths an event touched, as paths under the project directory
+fn collect_paths(
+    event: notify::Result<Event>,
+    root: &Path,
+    config: &AnalysisConfig,
+    changed: &mut Vec<PathBuf>,
+) {
+    let Ok(event) = event else {
+        return;
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+
+    for path in event.paths {
+        if let Ok(relative) = path.strip_prefix(root) {
+            changed.push(Path::new(&config.project_directory).join(relative));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, AccessMode, CreateKind, DataChange, ModifyKind};
+
+    fn changed_paths(events: Vec<notify::Result<Event>>) -> Vec<PathBuf> {
+        let config = AnalysisConfig::for_tests();
+        let mut changed = Vec::new();
+        for event in events {
+            collect_paths(event, Path::new("/srv/project"), &config, &mut changed);
+        }
+        changed
+    }
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn changes_are_reported_under_the_project_directory() {
+        let changed = changed_paths(vec![
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), "/srv/project/app.js"),
+            event(EventKind::Create(CreateKind::File), "/srv/project/routes/users.js"),
+        ]);
+
+        assert_eq!(changed, [Path::new("project/app.js"), Path::new("project/routes/users.js")]);
+    }
+
+    #[test]
+    fn reads_errors_and_paths_outside_the_root_are_ignored() {
+        let changed = changed_paths(vec![
+            event(EventKind::Access(AccessKind::Close(AccessMode::Write)), "/srv/project/app.js"),
+            event(EventKind::Access(AccessKind::Open(AccessMode::Read)), "/srv/project/app.js"),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), "/srv/other/app.js"),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), "/srv/project-old/app.js"),
+            Err(notify::Error::generic("watch limit reached")),
+        ]);
+
+        assert!(changed.is_empty(), "{:?}", changed);
+    }
+}